@@ -0,0 +1,29 @@
+use std::fs::read_to_string;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use markdown_split::{split_sections_with, Backend, SplitOptions};
+
+/// How many times each fixture is repeated, to get a text as large as a book.
+const REPEAT: usize = 100;
+
+fn backends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("split");
+    for fixture in ["ch01-01-installation.en.md", "ch01-01-installation.ja.md"] {
+        let text = read_to_string(format!("tests/fixtures/{fixture}"))
+            .unwrap()
+            .repeat(REPEAT);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        for backend in [Backend::Mdast, Backend::Scanner] {
+            let options = SplitOptions { backend, ..Default::default() };
+            group.bench_with_input(
+                BenchmarkId::new(format!("{backend:?}"), fixture),
+                &text,
+                |b, text| b.iter(|| split_sections_with(text, None, &options).unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, backends);
+criterion_main!(benches);
@@ -0,0 +1,364 @@
+use std::collections::{HashMap, HashSet};
+
+use markdown::{
+    mdast::{
+        Node,
+        Node::{Code, Definition, Image, ImageReference, Link, LinkReference},
+    },
+    ParseOptions,
+};
+
+use crate::{
+    error::Result,
+    options::SplitOptions,
+    section::Section,
+    split::{heading_in_comment, parse, sections_from_ast},
+};
+
+/// A section of a source text paired with its translation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignedPair<'a> {
+    /// The section in the source text.
+    pub source: Section<'a>,
+    /// The section in the translated text.
+    pub target: Section<'a>,
+    /// How confident the pairing is, from `0.0` to `1.0`. Sections paired by the original heading
+    /// commented out in the translation are always `1.0`.
+    pub confidence: f64,
+}
+
+/// The sections of a source text and of its translation, paired with each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment<'a> {
+    /// The paired sections, in the order of the translated text.
+    pub pairs: Vec<AlignedPair<'a>>,
+    /// The sections of the source text without a translation, e.g. not translated yet.
+    pub unmatched_source: Vec<Section<'a>>,
+    /// The sections of the translated text without a source, e.g. translator's notes.
+    pub unmatched_target: Vec<Section<'a>>,
+}
+
+/// The minimum confidence for two sections to be paired by their structure alone.
+const CONFIDENCE_THRESHOLD: f64 = 0.5;
+
+/// Pair the sections of a markdown text with the sections of its translation
+///
+/// Sections are paired by structure rather than by content, which differs across languages. A
+/// translated section whose heading is directly preceded by the original heading in an HTML
+/// comment, e.g. `<!-- ### Troubleshooting -->` as in the Rust book translations, is paired with
+/// the source section of that heading. The remaining sections are paired by the similarity of their
+/// heading depth, their relative position in the text, the contents of their code blocks and the
+/// targets of their links, which usually stay untranslated.
+///
+/// With [`SplitOptions::comments`], any comment right before a heading belongs to the heading's
+/// section, whether it holds an original heading or not. A comment at the very start of one text
+/// only, e.g. where the text comes from, thus leaves the other text with a section before the first
+/// heading which has no counterpart, and ends up in [`Alignment::unmatched_source`] or
+/// [`Alignment::unmatched_target`].
+///
+/// # Arguments
+///
+/// - `source`: The original markdown text.
+/// - `target`: The translated markdown text.
+/// - `options`: An optional `ParseOptions` struct to configure the markdown parser. If `None`,
+///   `ParseOptions::gfm()` (GitHub Flavored Markdown) is used.
+/// - `split_options`: Options to configure how both texts are split into sections.
+///
+/// # Returns
+///
+/// An [`Alignment`] of the paired sections and of the sections left unmatched on either side.
+///
+/// # Errors
+///
+/// Returns an error if either text is empty, if the depth range in `split_options` is invalid, or
+/// if either text cannot be parsed by the `markdown` crate.
+pub fn align<'a>(
+    source: &'a str,
+    target: &'a str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+) -> Result<Alignment<'a>> {
+    let source_ast = parse(source, options, split_options)?;
+    let target_ast = parse(target, options, split_options)?;
+    let source = Features::of(sections_from_ast(source, &source_ast, split_options), &source_ast);
+    let target = Features::of(sections_from_ast(target, &target_ast, split_options), &target_ast);
+
+    // For each target section, the index of the source section it is paired with and how confident
+    // the pairing is.
+    let mut pairs: Vec<Option<(usize, f64)>> = vec![None; target.len()];
+    let mut paired = vec![false; source.len()];
+
+    for (j, t) in target.iter().enumerate() {
+        let Some(original) = &t.original_heading else { continue };
+        let found = source.iter().enumerate().find(|(i, s)| {
+            !paired[*i] && s.section.heading.as_deref().map(str::trim) == Some(original.trim())
+        });
+        if let Some((i, _)) = found {
+            pairs[j] = Some((i, 1.0));
+            paired[i] = true;
+        }
+    }
+
+    let mut candidates = vec![];
+    for (j, t) in target.iter().enumerate().filter(|(j, _)| pairs[*j].is_none()) {
+        for (i, s) in source.iter().enumerate().filter(|(i, _)| !paired[*i]) {
+            let position = (i as f64 / source.len() as f64 - j as f64 / target.len() as f64).abs();
+            let confidence = s.similarity(t, 1.0 - position);
+            if confidence >= CONFIDENCE_THRESHOLD {
+                candidates.push((confidence, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (confidence, i, j) in candidates {
+        if !paired[i] && pairs[j].is_none() {
+            pairs[j] = Some((i, confidence));
+            paired[i] = true;
+        }
+    }
+
+    let mut alignment = Alignment {
+        pairs: vec![],
+        unmatched_source: vec![],
+        unmatched_target: vec![],
+    };
+    for (t, pair) in target.into_iter().zip(pairs) {
+        match pair {
+            Some((i, confidence)) => alignment.pairs.push(AlignedPair {
+                source: source[i].section.clone(),
+                target: t.section,
+                confidence,
+            }),
+            None => alignment.unmatched_target.push(t.section),
+        }
+    }
+    alignment.unmatched_source = source
+        .into_iter()
+        .zip(paired)
+        .filter_map(|(s, paired)| (!paired).then_some(s.section))
+        .collect();
+
+    Ok(alignment)
+}
+
+/// A section along with the structural features it is aligned by.
+struct Features<'a> {
+    section: Section<'a>,
+    /// The original heading commented out right before the heading of the section, if any.
+    original_heading: Option<String>,
+    /// The contents of the code blocks in the section.
+    code: HashSet<String>,
+    /// The targets of the links and images in the section.
+    links: HashSet<String>,
+}
+
+impl<'a> Features<'a> {
+    /// Collect the features of each section from the AST of the text.
+    fn of(sections: Vec<Section<'a>>, ast: &Node) -> Vec<Self> {
+        let mut features = sections
+            .into_iter()
+            .map(|section| Self {
+                section,
+                original_heading: None,
+                code: HashSet::new(),
+                links: HashSet::new(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut definitions = HashMap::new();
+        let mut nodes = vec![];
+        fn traverse<'n>(
+            node: &'n Node,
+            definitions: &mut HashMap<&'n str, &'n str>,
+            nodes: &mut Vec<&'n Node>,
+        ) {
+            if let Definition(d) = node {
+                definitions.entry(d.identifier.as_str()).or_insert(d.url.as_str());
+            }
+            nodes.push(node);
+            if let Some(children) = node.children() {
+                children.iter().for_each(|c| traverse(c, definitions, nodes));
+            }
+        }
+        traverse(ast, &mut definitions, &mut nodes);
+
+        // The sections follow each other, so the section of a node is the last one starting at or
+        // before it, unless it is in the front matter.
+        let starts = features.iter().map(|f| f.section.range.start).collect::<Vec<_>>();
+        for node in nodes {
+            let Some(position) = node.position() else { continue };
+            let offset = position.start.offset;
+            let Some(i) = starts.partition_point(|&start| start <= offset).checked_sub(1) else {
+                continue;
+            };
+            let f = &mut features[i];
+            if !f.section.range.contains(&offset) {
+                continue;
+            }
+            match node {
+                Code(code) => {
+                    f.code.insert(code.value.trim().to_string());
+                }
+                Link(link) => {
+                    f.links.insert(link.url.clone());
+                }
+                Image(image) => {
+                    f.links.insert(image.url.clone());
+                }
+                LinkReference(r) => f
+                    .links
+                    .extend(definitions.get(r.identifier.as_str()).map(|u| u.to_string())),
+                ImageReference(r) => f
+                    .links
+                    .extend(definitions.get(r.identifier.as_str()).map(|u| u.to_string())),
+                _ => {}
+            }
+        }
+
+        // The original heading is either in the section itself with `SplitOptions::comments`, or
+        // at the end of the previous section.
+        for i in 0..features.len() {
+            features[i].original_heading = features[i].section.id.clone();
+            if i > 0
+                && features[i].section.depth.is_some()
+                && features[i].original_heading.is_none()
+            {
+                features[i].original_heading = commented_heading(features[i - 1].section.text);
+            }
+        }
+
+        features
+    }
+
+    /// How similar the structure of two sections is, from `0.0` to `1.0`, given how close their
+    /// relative positions are. Features missing from both sections are left out.
+    fn similarity(&self, other: &Self, position: f64) -> f64 {
+        let mut scores = vec![(0.2, position)];
+        scores.push((0.2, if self.section.depth == other.section.depth { 1.0 } else { 0.0 }));
+        if !self.code.is_empty() || !other.code.is_empty() {
+            scores.push((0.35, jaccard(&self.code, &other.code)));
+        }
+        if !self.links.is_empty() || !other.links.is_empty() {
+            scores.push((0.25, jaccard(&self.links, &other.links)));
+        }
+        let total = scores.iter().map(|(weight, _)| weight).sum::<f64>();
+        scores.iter().map(|(weight, score)| weight * score).sum::<f64>() / total
+    }
+}
+
+/// The Jaccard index of two sets, from `0.0` to `1.0`.
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// The plain text of a heading commented out at the very end of a text, e.g.
+/// `<!--\n### Installing `rustup` on Windows\n-->`.
+fn commented_heading(text: &str) -> Option<String> {
+    let comment = text.trim_end().strip_suffix("-->")?;
+    heading_in_comment(&comment[comment.rfind("<!--")? + 4..])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        let en = read_to_string("tests/fixtures/ch01-01-installation.en.md").unwrap();
+        let ja = read_to_string("tests/fixtures/ch01-01-installation.ja.md").unwrap();
+
+        let alignment = align(&en, &ja, None, &SplitOptions::default()).unwrap();
+        assert_eq!(
+            alignment
+                .pairs
+                .iter()
+                .map(|p| (p.source.heading.as_deref(), p.target.heading.as_deref(), p.confidence))
+                .collect::<Vec<_>>(),
+            vec![
+                (None, None, 1.0),
+                (Some("Installation"), Some("インストール"), 1.0),
+                (
+                    Some("Installing rustup on Linux or macOS"),
+                    Some("LinuxとmacOSにrustupをインストールする"),
+                    1.0
+                ),
+                (
+                    Some("Installing rustup on Windows"),
+                    Some("Windowsでrustupをインストールする"),
+                    1.0
+                ),
+                (Some("Updating and Uninstalling"), Some("更新及びアンインストール"), 1.0),
+                (Some("Troubleshooting"), Some("トラブルシューティング"), 1.0),
+                (Some("Local Documentation"), Some("ローカルのドキュメンテーション"), 1.0),
+            ]
+        );
+        assert!(alignment.unmatched_source.is_empty());
+        assert!(alignment.unmatched_target.is_empty());
+
+        // The comment before each heading of the translation now belongs to the heading's section.
+        // So does the comment telling where the English text comes from, which leaves the text
+        // before the first heading of the translation, its own such comment, unmatched.
+        let options = SplitOptions { comments: true, ..Default::default() };
+        let alignment = align(&en, &ja, None, &options).unwrap();
+        assert_eq!(alignment.pairs.len(), 6);
+        assert!(alignment
+            .pairs
+            .iter()
+            .all(|p| p.confidence == 1.0 && p.source.heading == p.target.id));
+        assert!(alignment.pairs[0].source.text.starts_with("<!-- This is from"));
+        assert!(alignment.unmatched_source.is_empty());
+        assert_eq!(alignment.unmatched_target.len(), 1);
+        assert_eq!(alignment.unmatched_target[0].heading, None);
+        assert!(alignment.unmatched_target[0].text.starts_with("<!-- This is from"));
+    }
+
+    #[test]
+    fn test_structure() {
+        let source = "# Install\n\n```\n$ cargo install\n```\n\n# Update\n\n```\n$ rustup update\n```\n\n# Help\n\nSee [the forum](https://users.rust-lang.org/).\n\n# Extra\n\nMore.\n";
+        let target = "# Mettre à jour\n\n```\n$ rustup update\n```\n\n# Installer\n\n```\n$ cargo install\n```\n\n# Aide\n\nVoir [le forum](https://users.rust-lang.org/).\n\n## Note du traducteur\n\n```\nfoo\n```\n";
+
+        let alignment = align(source, target, None, &SplitOptions::default()).unwrap();
+        assert_eq!(
+            alignment
+                .pairs
+                .iter()
+                .map(|p| (p.source.heading.as_deref(), p.target.heading.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("Update"), Some("Mettre à jour")),
+                (Some("Install"), Some("Installer")),
+                (Some("Help"), Some("Aide")),
+            ]
+        );
+        assert_eq!(alignment.unmatched_source[0].heading.as_deref(), Some("Extra"));
+        assert_eq!(alignment.unmatched_target[0].heading.as_deref(), Some("Note du traducteur"));
+    }
+
+    #[test]
+    fn test_commented_heading() {
+        assert_eq!(
+            commented_heading("Text\n\n<!--\n### Installing `rustup` on Windows\n-->\n\n"),
+            Some("Installing rustup on Windows".to_string())
+        );
+        assert_eq!(commented_heading("<!-- ## Installation -->"), Some("Installation".to_string()));
+        assert_eq!(commented_heading("<!--\n> ### Notation\n-->\n"), None);
+        assert_eq!(commented_heading("<!-- ## A -->\n\nText\n"), None);
+
+        // A comment the `markdown` crate panics on has no heading, rather than failing the
+        // alignment.
+        let source = "# A\n\nx\n\n<!--\n[a]: b\n---\nx\n---\n-->\n# B\n";
+        let target = "# A\n\ny\n";
+        assert_eq!(commented_heading(&source[..source.len() - 4]), None);
+        for (source, target) in [(source, target), (target, source)] {
+            let alignment = align(source, target, None, &SplitOptions::default()).unwrap();
+            assert_eq!(alignment.pairs[0].source.heading.as_deref(), Some("A"));
+            assert_eq!(alignment.pairs[0].target.heading.as_deref(), Some("A"));
+        }
+    }
+}
@@ -0,0 +1,444 @@
+use std::{
+    fs::read_to_string,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use log::debug;
+use markdown::{Constructs, ParseOptions};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use walkdir::WalkDir;
+
+use crate::{
+    error::{Error, Result},
+    front_matter::{FrontMatter, FrontMatterKind},
+    location::Location,
+    options::SplitOptions,
+    section::Section,
+    split::split_document,
+};
+
+/// Options to configure which files of a directory tree are split, and how.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Glob patterns of the files to split, relative to the directory, e.g. `docs/**/*.md`. `*`
+    /// does not match `/`, while `**` matches any number of directories.
+    pub include: Vec<String>,
+    /// Glob patterns of the files and directories to skip, relative to the directory, e.g.
+    /// `**/node_modules`. A skipped directory is not walked at all.
+    pub exclude: Vec<String>,
+    /// Options to configure how each file is split into sections.
+    pub split: SplitOptions,
+}
+
+impl Default for BatchOptions {
+    /// Split every `.md` file on every heading.
+    fn default() -> Self {
+        Self {
+            include: vec!["**/*.md".to_string()],
+            exclude: vec![],
+            split: SplitOptions::default(),
+        }
+    }
+}
+
+/// The documents of the files of a directory tree, and the files which could not be split.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Batch {
+    /// The documents of the files which were split, in the order of their paths.
+    pub files: Vec<FileDocument>,
+    /// The files which could not be read or split, in the order of their paths.
+    pub errors: Vec<FileError>,
+}
+
+/// A file split by [`split_dir`] into its front matter and its sections, as by
+/// [`split_document`](crate::split_document), but owning its text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileDocument {
+    /// The path of the file, which starts with the directory it was found in.
+    pub path: PathBuf,
+    /// The front matter at the start of the file, if any.
+    pub front_matter: Option<FileFrontMatter>,
+    /// The sections of the file following the front matter.
+    pub sections: Vec<FileSection>,
+}
+
+/// The front matter of a file split by [`split_dir`], see [`FrontMatter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileFrontMatter {
+    /// The format of the front matter.
+    pub kind: FrontMatterKind,
+    /// The text of the file covered by the front matter, including its fences and the blank lines
+    /// following it.
+    pub text: String,
+    /// The content of the front matter, without its fences.
+    pub raw: String,
+    /// The byte range of the front matter in the file.
+    pub range: Range<usize>,
+}
+
+impl From<FrontMatter<'_>> for FileFrontMatter {
+    fn from(front_matter: FrontMatter) -> Self {
+        Self {
+            kind: front_matter.kind,
+            text: front_matter.text.to_string(),
+            raw: front_matter.raw.to_string(),
+            range: front_matter.range,
+        }
+    }
+}
+
+/// A section of a file split by [`split_dir`], with the same fields as a [`Section`] but owning
+/// its text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileSection {
+    /// The text of the file covered by this section, including the heading.
+    pub text: String,
+    /// The depth of the heading (`1` for h1 to `6` for h6), or `None` for the text before the
+    /// first heading.
+    pub depth: Option<u8>,
+    /// The plain text of the heading, or `None` for the text before the first heading. See
+    /// [`Section::heading`].
+    pub heading: Option<String>,
+    /// A stable identifier of this section, see [`Section::id`].
+    pub id: Option<String>,
+    /// The anchor of the heading, unique within the file. See [`Section::slug`].
+    pub slug: Option<String>,
+    /// The plain text of the headings of the enclosing sections and of this section, from the
+    /// outermost to this one. Not to be confused with the path of the file,
+    /// [`FileDocument::path`].
+    pub path: Vec<String>,
+    /// The byte range of the heading in the file. See [`Section::heading_range`].
+    pub heading_range: Option<Range<usize>>,
+    /// The link reference definitions used in this section but defined outside of it. See
+    /// [`Section::definitions`].
+    pub definitions: Vec<String>,
+    /// The footnote definitions referenced in this section but defined outside of it. See
+    /// [`Section::footnotes`].
+    pub footnotes: Vec<String>,
+    /// The byte range of this section in the file.
+    pub range: Range<usize>,
+    /// The location where this section starts in the file.
+    pub start: Location,
+    /// The location where this section ends in the file, exclusive.
+    pub end: Location,
+}
+
+impl From<Section<'_>> for FileSection {
+    fn from(section: Section) -> Self {
+        let owned = |definitions: Vec<&str>| definitions.into_iter().map(str::to_string).collect();
+        Self {
+            text: section.text.to_string(),
+            depth: section.depth,
+            heading: section.heading,
+            id: section.id,
+            slug: section.slug,
+            path: section.path,
+            heading_range: section.heading_range,
+            definitions: owned(section.definitions),
+            footnotes: owned(section.footnotes),
+            range: section.range,
+            start: section.start,
+            end: section.end,
+        }
+    }
+}
+
+/// A file, or a directory, which could not be read or split by [`split_dir`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileError {
+    /// The path of the file or the directory, which starts with the directory it was found in.
+    pub path: PathBuf,
+    /// Why it could not be read or split, serialized as its message.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_error"))]
+    pub error: Error,
+}
+
+/// Serialize an error as its message, as [`Error`] is not serializable itself.
+#[cfg(feature = "serde")]
+fn serialize_error<S: serde::Serializer>(error: &Error, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(error)
+}
+
+/// Split every markdown file of a directory tree into sections based on headings
+///
+/// The directory is walked recursively, following neither symbolic links nor the directories
+/// matching [`BatchOptions::exclude`], and each file matching [`BatchOptions::include`] is split
+/// as by [`split_document`](crate::split_document). With the `rayon` feature, the files are split
+/// in parallel.
+///
+/// An error on a file, e.g. because it is empty or cannot be read, does not stop the batch: it is
+/// collected in [`Batch::errors`] along with the path of the file.
+///
+/// # Arguments
+///
+/// - `dir`: The directory to walk.
+/// - `options`: An optional `ParseOptions` struct to configure the markdown parser. If `None`,
+///   `ParseOptions::gfm()` (GitHub Flavored Markdown) is used. The MDX hooks are ignored, as they
+///   cannot be shared across threads.
+/// - `batch_options`: Which files to split, and how.
+///
+/// # Returns
+///
+/// The front matter and the sections of every file, along with its path, and the errors of the
+/// files which could not be split.
+///
+/// # Errors
+///
+/// Returns an error if a glob pattern is invalid.
+///
+/// ```no_run
+/// use markdown_split::{split_dir, BatchOptions};
+///
+/// let options = BatchOptions {
+///     exclude: vec!["drafts".to_string()],
+///     ..Default::default()
+/// };
+/// let batch = split_dir("docs", None, &options)?;
+/// for error in &batch.errors {
+///     eprintln!("{}: {}", error.path.display(), error.error);
+/// }
+/// # Ok::<(), markdown_split::Error>(())
+/// ```
+pub fn split_dir(
+    dir: impl AsRef<Path>,
+    options: Option<&ParseOptions>,
+    batch_options: &BatchOptions,
+) -> Result<Batch> {
+    let dir = dir.as_ref();
+    let include = glob_set(&batch_options.include)?;
+    let exclude = glob_set(&batch_options.exclude)?;
+    let relative = |path: &Path| path.strip_prefix(dir).unwrap_or(path).to_path_buf();
+
+    let mut batch = Batch::default();
+    let mut files = vec![];
+    let entries = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !exclude.is_match(relative(entry.path())));
+    for entry in entries {
+        match entry {
+            Ok(entry)
+                if entry.file_type().is_file() && include.is_match(relative(entry.path())) =>
+            {
+                files.push(entry.into_path());
+            }
+            Ok(_) => {}
+            Err(error) => batch.errors.push(FileError {
+                path: error.path().unwrap_or(dir).to_path_buf(),
+                error: Error::Io(error.into()),
+            }),
+        }
+    }
+    debug!("Found {} files to split in {}", files.len(), dir.display());
+
+    let parse = Parse::new(options);
+    let split = |path: &PathBuf| split_file(path, &parse, &batch_options.split);
+    #[cfg(feature = "rayon")]
+    let results = files.par_iter().map(split).collect::<Vec<_>>();
+    #[cfg(not(feature = "rayon"))]
+    let results = files.iter().map(split).collect::<Vec<_>>();
+
+    for (path, result) in files.into_iter().zip(results) {
+        match result {
+            Ok(document) => batch.files.push(document),
+            Err(error) => batch.errors.push(FileError { path, error }),
+        }
+    }
+    batch.errors.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(batch)
+}
+
+/// Read a file and split it into its owned front matter and sections.
+fn split_file(path: &Path, parse: &Parse, split_options: &SplitOptions) -> Result<FileDocument> {
+    let text = read_to_string(path)?;
+    let document = split_document(&text, Some(&parse.options()), split_options)?;
+    Ok(FileDocument {
+        path: path.to_path_buf(),
+        front_matter: document.front_matter.map(FileFrontMatter::from),
+        sections: document.sections.into_iter().map(FileSection::from).collect(),
+    })
+}
+
+/// Match paths against any of the glob patterns, where `*` does not match `/`.
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+    }
+    Ok(builder.build()?)
+}
+
+/// The `ParseOptions` to parse every file with, without the MDX hooks which are neither `Send` nor
+/// `Sync`, so that each thread can build its own.
+struct Parse {
+    constructs: Constructs,
+    gfm_strikethrough_single_tilde: bool,
+    math_text_single_dollar: bool,
+}
+
+impl Parse {
+    fn new(options: Option<&ParseOptions>) -> Self {
+        let options = if let Some(o) = options { o } else { &ParseOptions::gfm() };
+        Self {
+            constructs: options.constructs.clone(),
+            gfm_strikethrough_single_tilde: options.gfm_strikethrough_single_tilde,
+            math_text_single_dollar: options.math_text_single_dollar,
+        }
+    }
+
+    fn options(&self) -> ParseOptions {
+        ParseOptions {
+            constructs: self.constructs.clone(),
+            gfm_strikethrough_single_tilde: self.gfm_strikethrough_single_tilde,
+            math_text_single_dollar: self.math_text_single_dollar,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, remove_dir_all, write},
+    };
+
+    use super::*;
+    use crate::split::split_sections_with;
+
+    /// A directory tree of markdown files, removed when dropped.
+    struct Tree(PathBuf);
+
+    impl Tree {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let dir = temp_dir().join(format!("markdown-split-{name}-{}", std::process::id()));
+            for (path, text) in files {
+                let path = dir.join(path);
+                create_dir_all(path.parent().unwrap()).unwrap();
+                write(path, text).unwrap();
+            }
+            Self(dir)
+        }
+    }
+
+    impl Drop for Tree {
+        fn drop(&mut self) {
+            let _ = remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_split_dir() {
+        let tree = Tree::new(
+            "split",
+            &[
+                ("b.md", "# B\n\n[b]\n\n## C\n\n[b]: https://example.com\n"),
+                ("a/a.md", "---\ntitle: A\n---\nIntro\n\n# A {#a}\n"),
+                ("a/empty.md", ""),
+                ("a/notes.txt", "# Not markdown\n"),
+                ("drafts/d.md", "# D\n"),
+            ],
+        );
+        let options = BatchOptions {
+            exclude: vec!["drafts".to_string()],
+            split: SplitOptions {
+                front_matter: true,
+                definitions: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let batch = split_dir(&tree.0, None, &options).unwrap();
+
+        assert_eq!(
+            batch
+                .files
+                .iter()
+                .map(|f| (
+                    relative(&tree, &f.path),
+                    f.sections
+                        .iter()
+                        .map(|s| (s.heading.as_deref(), s.slug.as_deref()))
+                        .collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("a/a.md".to_string(), vec![(None, None), (Some("A"), Some("a"))]),
+                ("b.md".to_string(), vec![(Some("B"), Some("b")), (Some("C"), Some("c"))]),
+            ]
+        );
+
+        // Every field of the sections is kept, as is the front matter.
+        let front_matter = batch.files[0].front_matter.as_ref().unwrap();
+        assert_eq!(front_matter.raw, "title: A");
+        assert_eq!(batch.files[0].sections[1].id.as_deref(), Some("a"));
+        assert_eq!(batch.files[0].sections[1].heading_range, Some(24..32));
+        let text = read_to_string(tree.0.join("b.md")).unwrap();
+        let sections = split_sections_with(&text, None, &options.split).unwrap();
+        let expected = sections.into_iter().map(FileSection::from).collect::<Vec<_>>();
+        assert_eq!(batch.files[1].sections, expected);
+        assert_eq!(batch.files[1].sections[0].definitions, ["[b]: https://example.com"]);
+
+        // The empty file is reported, but does not stop the batch.
+        assert_eq!(batch.errors.len(), 1);
+        assert_eq!(relative(&tree, &batch.errors[0].path), "a/empty.md");
+        assert!(matches!(batch.errors[0].error, Error::EmptyInput));
+    }
+
+    #[test]
+    fn test_include() {
+        let tree = Tree::new(
+            "include",
+            &[("a.md", "# A\n"), ("b/b.md", "# B\n"), ("c.markdown", "# C\n")],
+        );
+        let options = BatchOptions {
+            include: vec!["*.md".to_string(), "*.markdown".to_string()],
+            ..Default::default()
+        };
+        let batch = split_dir(&tree.0, None, &options).unwrap();
+        assert_eq!(
+            batch
+                .files
+                .iter()
+                .map(|f| relative(&tree, &f.path))
+                .collect::<Vec<_>>(),
+            ["a.md", "c.markdown"]
+        );
+
+        let options = BatchOptions {
+            include: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(split_dir(&tree.0, None, &options), Err(Error::InvalidGlob(_))));
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_serialize() {
+        let tree = Tree::new("serialize", &[("a.md", "# A\n"), ("b.md", "")]);
+        let batch = split_dir(&tree.0, None, &BatchOptions::default()).unwrap();
+
+        let value = serde_json::to_value(&batch).unwrap();
+        assert_eq!(value["files"][0]["sections"][0]["slug"], "a");
+        assert_eq!(value["files"][0]["front_matter"], serde_json::Value::Null);
+        assert_eq!(value["errors"][0]["error"], Error::EmptyInput.to_string());
+    }
+
+    /// The path of a file relative to the tree, with `/` as separator.
+    fn relative(tree: &Tree, path: &Path) -> String {
+        let path = path.strip_prefix(&tree.0).unwrap();
+        path.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
@@ -0,0 +1,283 @@
+use std::ops::Range;
+
+use log::debug;
+use markdown::{
+    mdast::{
+        Node,
+        Node::{Blockquote, ListItem, Paragraph, Root, Text},
+    },
+    ParseOptions,
+};
+
+use crate::{
+    error::{Error, Result},
+    options::SplitOptions,
+    sizer::{Bytes, Sizer},
+    split::{parse, sections_from_ast},
+};
+
+/// Options to configure how a markdown text is chunked.
+#[derive(Debug, Clone)]
+pub struct ChunkOptions<S = Bytes> {
+    /// The maximum size of a chunk, as measured by `sizer`.
+    pub max_size: usize,
+    /// How the size of a chunk is measured.
+    pub sizer: S,
+    /// Whether neighbouring sections which fit together within `max_size` are merged into a single
+    /// chunk.
+    pub merge: bool,
+    /// Options to configure which headings the initial sections are split on.
+    pub split: SplitOptions,
+}
+
+impl ChunkOptions {
+    /// Chunks of at most `max_size` bytes, merging small neighbouring sections.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            sizer: Bytes,
+            merge: true,
+            split: SplitOptions::default(),
+        }
+    }
+}
+
+impl<S> ChunkOptions<S> {
+    /// Measure the size of chunks with another [`Sizer`].
+    pub fn with_sizer<T: Sizer>(self, sizer: T) -> ChunkOptions<T> {
+        ChunkOptions {
+            max_size: self.max_size,
+            sizer,
+            merge: self.merge,
+            split: self.split,
+        }
+    }
+}
+
+/// Split a markdown text into chunks no larger than a maximum size
+///
+/// The text is first split into sections based on headings. Sections larger than the maximum size
+/// are split further at the boundaries of top-level blocks such as paragraphs, then at list items
+/// and nested blocks, then at sentences, trying each level only when the previous one did not
+/// suffice. Neighbouring pieces are packed together as long as they fit.
+///
+/// A chunk may still exceed the maximum size when it contains no boundary at all, e.g. a single
+/// long code block or sentence.
+///
+/// # Arguments
+///
+/// - `text`: A string slice containing the markdown text to chunk.
+/// - `options`: An optional `ParseOptions` struct to configure the markdown parser. If `None`,
+///   `ParseOptions::gfm()` (GitHub Flavored Markdown) is used.
+/// - `chunk_options`: The maximum size of a chunk and the [`Sizer`] it is measured with.
+///
+/// # Returns
+///
+/// A vector of string slices of the original markdown text which, concatenated, reproduce it, minus
+/// the front matter if [`SplitOptions::front_matter`] is set.
+///
+/// # Errors
+///
+/// Returns an error if the markdown text is empty, if `max_size` is zero, if the depth range is
+/// invalid, or if the text cannot be parsed by the `markdown` crate.
+pub fn chunk<'a, S: Sizer>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+    chunk_options: &ChunkOptions<S>,
+) -> Result<Vec<&'a str>> {
+    if chunk_options.max_size == 0 {
+        return Err(Error::InvalidChunkSize);
+    }
+
+    let ast = parse(text, options, &chunk_options.split)?;
+    let chunker = Chunker {
+        text,
+        options: chunk_options,
+        boundaries: find_boundaries(text, &ast),
+    };
+
+    let pieces = sections_from_ast(text, &ast, &chunk_options.split)
+        .into_iter()
+        .map(|section| chunker.split(section.range, 0))
+        .collect::<Vec<_>>();
+    let ranges = if chunk_options.merge {
+        chunker.pack(pieces.into_iter().flatten())
+    } else {
+        pieces.into_iter().flatten().collect()
+    };
+    debug!("Found {} chunks", ranges.len());
+
+    Ok(ranges.into_iter().map(|r| &text[r]).collect())
+}
+
+struct Chunker<'a, 'o, S> {
+    text: &'a str,
+    options: &'o ChunkOptions<S>,
+    /// Candidate split offsets for each level, from the coarsest to the finest, sorted.
+    boundaries: [Vec<usize>; 3],
+}
+
+impl<S: Sizer> Chunker<'_, '_, S> {
+    fn size(&self, range: &Range<usize>) -> usize {
+        self.options.sizer.size(&self.text[range.clone()])
+    }
+
+    fn fits(&self, range: &Range<usize>) -> bool {
+        self.size(range) <= self.options.max_size
+    }
+
+    /// Split a range at the boundaries of the given level, falling back to finer levels for the
+    /// pieces which are still too large.
+    fn split(&self, range: Range<usize>, level: usize) -> Vec<Range<usize>> {
+        if self.fits(&range) || level >= self.boundaries.len() {
+            return vec![range];
+        }
+
+        let boundaries = &self.boundaries[level];
+        let from = boundaries.partition_point(|&b| b <= range.start);
+        let to = boundaries.partition_point(|&b| b < range.end);
+        if from == to {
+            return self.split(range, level + 1);
+        }
+
+        let mut points = vec![range.start];
+        points.extend_from_slice(&boundaries[from..to]);
+        points.push(range.end);
+        let pieces = points.windows(2).map(|w| w[0]..w[1]).flat_map(|piece| {
+            if self.fits(&piece) {
+                vec![piece]
+            } else {
+                self.split(piece, level + 1)
+            }
+        });
+
+        self.pack(pieces)
+    }
+
+    /// Greedily merge contiguous ranges as long as the result fits.
+    fn pack(&self, ranges: impl IntoIterator<Item = Range<usize>>) -> Vec<Range<usize>> {
+        let mut packed: Vec<Range<usize>> = vec![];
+        for range in ranges {
+            match packed.last_mut() {
+                Some(last) if self.fits(&(last.start..range.end)) => last.end = range.end,
+                _ => packed.push(range),
+            }
+        }
+        packed
+    }
+}
+
+/// Find the offsets at which an oversized section may be split, in three levels: top-level blocks,
+/// list items and blocks nested in containers, and sentences.
+fn find_boundaries(text: &str, ast: &Node) -> [Vec<usize>; 3] {
+    let mut boundaries: [Vec<usize>; 3] = Default::default();
+
+    fn traverse(text: &str, node: &Node, parent: Option<&Node>, boundaries: &mut [Vec<usize>; 3]) {
+        if let Some(position) = node.position() {
+            match (parent, node) {
+                (Some(Root(_)), _) => boundaries[0].extend(line_start(text, position.start.offset)),
+                (Some(Blockquote(_) | ListItem(_)), _) | (_, ListItem(_)) => {
+                    boundaries[1].extend(line_start(text, position.start.offset))
+                }
+                (Some(Paragraph(_)), Text(_)) => {
+                    let range = position.start.offset..position.end.offset;
+                    boundaries[2]
+                        .extend(sentence_ends(&text[range.clone()]).map(|o| range.start + o));
+                }
+                _ => {}
+            }
+        }
+        if let Some(children) = node.children() {
+            children
+                .iter()
+                .for_each(|c| traverse(text, c, Some(node), boundaries));
+        }
+    }
+    traverse(text, ast, None, &mut boundaries);
+
+    boundaries.iter_mut().for_each(|b| {
+        b.sort_unstable();
+        b.dedup();
+    });
+    boundaries
+}
+
+/// Move an offset back to the start of its line, if it is only preceded by indentation or
+/// blockquote markers, so that the markers stay with the block they belong to. Returns `None` if
+/// the offset is preceded by anything else, e.g. a list item marker.
+fn line_start(text: &str, offset: usize) -> Option<usize> {
+    let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    text[start..offset]
+        .chars()
+        .all(|c| c.is_whitespace() || c == '>')
+        .then_some(start)
+}
+
+/// Find the offsets where a sentence starts after the end of the previous one in a text.
+fn sentence_ends(text: &str) -> impl Iterator<Item = usize> + '_ {
+    text.char_indices().filter_map(move |(i, c)| {
+        let after = i + c.len_utf8();
+        let rest = &text[after..];
+        let next = after + (rest.len() - rest.trim_start().len());
+        match c {
+            '.' | '!' | '?' if next > after && next < text.len() => Some(next),
+            '。' | '！' | '？' if next < text.len() => Some(next),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+    use crate::sizer::Chars;
+
+    #[test]
+    fn test_en() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.en.md").unwrap();
+
+        let chunks = chunk(&text, None, &ChunkOptions::new(800)).unwrap();
+        assert!(chunks.iter().all(|c| c.len() <= 800));
+        assert_eq!(chunks.concat(), text);
+        assert!(chunks[0].starts_with("<!-- This is from"));
+        assert!(chunks[0].contains("## Installation\n\n"));
+        assert!(chunks[1].starts_with("The following steps install"));
+    }
+
+    #[test]
+    fn test_ja_chars() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.ja.md").unwrap();
+
+        let options = ChunkOptions::new(600).with_sizer(Chars);
+        let chunks = chunk(&text, None, &options).unwrap();
+        assert!(chunks.iter().all(|c| c.chars().count() <= 600));
+        assert!(chunks.iter().any(|c| c.len() > 600));
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_fallbacks() {
+        let text = "# A\n\nOne. Two. Three.\n\n- four\n- five\n";
+
+        let chunks = chunk(text, None, &ChunkOptions::new(12)).unwrap();
+        assert_eq!(chunks, vec!["# A\n\n", "One. Two. ", "Three.\n\n", "- four\n", "- five\n"]);
+
+        let options = ChunkOptions { merge: false, ..ChunkOptions::new(100) };
+        let chunks = chunk(text, None, &options).unwrap();
+        assert_eq!(chunks, vec![text]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let text = "# A\n\na\n\n## B\n\nb\n\n## C\n\nc\n";
+
+        let chunks = chunk(text, None, &ChunkOptions::new(20)).unwrap();
+        assert_eq!(chunks, vec!["# A\n\na\n\n## B\n\nb\n\n", "## C\n\nc\n"]);
+
+        let options = ChunkOptions { merge: false, ..ChunkOptions::new(20) };
+        let chunks = chunk(text, None, &options).unwrap();
+        assert_eq!(chunks, vec!["# A\n\na\n\n", "## B\n\nb\n\n", "## C\n\nc\n"]);
+    }
+}
@@ -0,0 +1,59 @@
+use ::comrak::{
+    nodes::{AstNode, NodeValue},
+    parse_document, Arena, Options,
+};
+use markdown::ParseOptions;
+
+use crate::{scan::Lines, source::Heading};
+
+/// The headings of a markdown text, as parsed by `comrak` with its GFM extensions.
+pub(crate) fn headings(text: &str, options: &ParseOptions) -> Vec<Heading> {
+    let mut extensions = Options::default();
+    extensions.extension.strikethrough = true;
+    extensions.extension.table = true;
+    extensions.extension.autolink = true;
+    extensions.extension.tasklist = true;
+    extensions.extension.footnotes = true;
+    if options.constructs.frontmatter {
+        let fence = if text.starts_with("+++") { "+++" } else { "---" };
+        extensions.extension.front_matter_delimiter = Some(fence.to_string());
+    }
+
+    // The offset of each line, as positions in the AST are lines and columns, from 1.
+    let lines = Lines::new(text).map(|(offset, _)| offset).collect::<Vec<_>>();
+    let arena = Arena::new();
+    let root = parse_document(&arena, text, &extensions);
+    root.descendants()
+        .filter_map(|node| {
+            let ast = node.data.borrow();
+            let NodeValue::Heading(heading) = ast.value else {
+                return None;
+            };
+            let (start, end) = (ast.sourcepos.start, ast.sourcepos.end);
+            let start = *lines.get(start.line - 1)?;
+            let end = lines.get(end.line - 1)? + end.column;
+            let mut plain = String::new();
+            plain_text(node, &mut plain);
+            Some(Heading {
+                range: start..end.min(text.len()),
+                depth: heading.level,
+                text: plain,
+                nested: !node.parent().is_some_and(|parent| parent.same_node(root)),
+            })
+        })
+        .collect()
+}
+
+/// Append the plain text of a node to `plain`, without the alt text of images.
+fn plain_text<'a>(node: &'a AstNode<'a>, plain: &mut String) {
+    for child in node.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(value) | NodeValue::HtmlInline(value) => plain.push_str(value),
+            NodeValue::Code(code) => plain.push_str(&code.literal),
+            NodeValue::Math(math) => plain.push_str(&math.literal),
+            NodeValue::SoftBreak => plain.push('\n'),
+            NodeValue::Image(_) => {}
+            _ => plain_text(child, plain),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::{collections::HashMap, hash::Hash, ops::Range};
+
+use markdown::ParseOptions;
+
+use crate::{error::Result, options::SplitOptions, section::Section, split::split_sections_with};
+
+/// How a section changed between two versions of a markdown text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
+pub enum SectionDiff<'a> {
+    /// A section which only exists in the new version.
+    Added(Section<'a>),
+    /// A section which only exists in the old version.
+    Removed(Section<'a>),
+    /// A section which exists in both versions, possibly changed.
+    Matched {
+        /// The section in the old version.
+        old: Section<'a>,
+        /// The section in the new version.
+        new: Section<'a>,
+        /// Whether the content of the section, other than its heading, changed.
+        modified: bool,
+        /// Whether the section moved relative to the other matched sections, or under another
+        /// parent heading.
+        moved: bool,
+        /// Whether the heading of the section changed.
+        renamed: bool,
+    },
+}
+
+impl SectionDiff<'_> {
+    /// Whether the section is the same in both versions, i.e. neither added, removed, modified,
+    /// moved nor renamed.
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, SectionDiff::Matched { modified: false, moved: false, renamed: false, .. })
+    }
+}
+
+/// The minimum similarity of the content of two sections with different headings for them to be
+/// considered the same, renamed section.
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/// Compare two versions of a markdown text section by section
+///
+/// Both texts are split into sections, which are matched by their heading path first, then by their
+/// heading alone, and finally by the similarity of their content, so that renamed sections are
+/// still recognized as such.
+///
+/// # Arguments
+///
+/// - `old`: The old version of the markdown text.
+/// - `new`: The new version of the markdown text.
+/// - `options`: An optional `ParseOptions` struct to configure the markdown parser. If `None`,
+///   `ParseOptions::gfm()` (GitHub Flavored Markdown) is used.
+/// - `split_options`: Options to configure how both texts are split into sections.
+///
+/// # Returns
+///
+/// A vector of [`SectionDiff`]s, including unchanged sections, in the order of the new version.
+/// Removed sections are placed after the section preceding them in the old version.
+///
+/// # Errors
+///
+/// Returns an error if either text is empty, if the depth range in `split_options` is invalid, or
+/// if either text cannot be parsed by the `markdown` crate.
+pub fn diff<'a>(
+    old: &'a str,
+    new: &'a str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+) -> Result<Vec<SectionDiff<'a>>> {
+    let old = split_sections_with(old, options, split_options)?;
+    let new = split_sections_with(new, options, split_options)?;
+
+    // For each new section, the index of the old section it matches, if any.
+    let mut matches: Vec<Option<usize>> = vec![None; new.len()];
+    match_by(&old, &new, &mut matches, |s| Some(s.path.clone()));
+    match_by(&old, &new, &mut matches, |s| s.heading.clone());
+    match_by_similarity(&old, &new, &mut matches);
+
+    let in_order = longest_increasing_subsequence(&matches);
+    let mut removed = vec![true; old.len()];
+    matches.iter().flatten().for_each(|&i| removed[i] = false);
+
+    let mut diffs = vec![];
+    for (j, new) in new.into_iter().enumerate() {
+        let Some(i) = matches[j] else {
+            diffs.push(SectionDiff::Added(new));
+            continue;
+        };
+        let in_order = in_order.contains(&j);
+        if in_order {
+            emit_removed(&old, &mut removed, 0..i, &mut diffs);
+        }
+        let matched = &old[i];
+        diffs.push(SectionDiff::Matched {
+            modified: body(matched).trim_end() != body(&new).trim_end(),
+            moved: !in_order || parent(matched) != parent(&new),
+            renamed: matched.heading != new.heading,
+            old: matched.clone(),
+            new,
+        });
+        if in_order {
+            let end = (i + 1..removed.len()).find(|&k| !removed[k]).unwrap_or(removed.len());
+            emit_removed(&old, &mut removed, i + 1..end, &mut diffs);
+        }
+    }
+    emit_removed(&old, &mut removed, 0..old.len(), &mut diffs);
+
+    Ok(diffs)
+}
+
+/// Add the removed sections within a range of old sections to the diffs, once.
+fn emit_removed<'a>(
+    old: &[Section<'a>],
+    removed: &mut [bool],
+    range: Range<usize>,
+    diffs: &mut Vec<SectionDiff<'a>>,
+) {
+    for k in range {
+        if removed[k] {
+            diffs.push(SectionDiff::Removed(old[k].clone()));
+            removed[k] = false;
+        }
+    }
+}
+
+/// Match the sections which are not matched yet and have the same key, in document order.
+fn match_by<K, F>(old: &[Section], new: &[Section], matches: &mut [Option<usize>], key: F)
+where
+    K: Eq + Hash,
+    F: Fn(&Section) -> Option<K>,
+{
+    let mut matched = vec![false; old.len()];
+    matches.iter().flatten().for_each(|&i| matched[i] = true);
+
+    let mut candidates: HashMap<K, Vec<usize>> = HashMap::new();
+    for (i, section) in old.iter().enumerate().rev().filter(|(i, _)| !matched[*i]) {
+        if let Some(key) = key(section) {
+            candidates.entry(key).or_default().push(i);
+        }
+    }
+    for (j, section) in new.iter().enumerate() {
+        if matches[j].is_none() {
+            matches[j] = key(section)
+                .and_then(|k| candidates.get_mut(&k))
+                .and_then(|c| c.pop());
+        }
+    }
+}
+
+/// Match the remaining sections whose content is similar enough, the most similar pairs first.
+fn match_by_similarity(old: &[Section], new: &[Section], matches: &mut [Option<usize>]) {
+    let mut matched = vec![false; old.len()];
+    matches.iter().flatten().for_each(|&i| matched[i] = true);
+
+    let mut pairs = vec![];
+    for (j, n) in new.iter().enumerate().filter(|(j, _)| matches[*j].is_none()) {
+        for (i, o) in old.iter().enumerate().filter(|(i, _)| !matched[*i]) {
+            let similarity = similarity(body(o), body(n));
+            if similarity >= SIMILARITY_THRESHOLD {
+                pairs.push((similarity, i, j));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (_, i, j) in pairs {
+        if !matched[i] && matches[j].is_none() {
+            matched[i] = true;
+            matches[j] = Some(i);
+        }
+    }
+}
+
+/// The content of a section after its heading, which may span several lines with a setext
+/// underline, leaving out whatever precedes the heading, e.g. a comment.
+fn body<'a>(section: &Section<'a>) -> &'a str {
+    match &section.heading_range {
+        Some(range) => {
+            // The heading ends before the trailing whitespace and the line ending of its line.
+            let body = &section.text[range.end - section.range.start..];
+            let body = body.trim_start_matches([' ', '\t']);
+            body.strip_prefix("\r\n")
+                .or_else(|| body.strip_prefix(['\n', '\r']))
+                .unwrap_or(body)
+        }
+        None => section.text,
+    }
+}
+
+/// The heading path of the parent of a section.
+fn parent<'s>(section: &'s Section) -> &'s [String] {
+    &section.path[..section.path.len().saturating_sub(1)]
+}
+
+/// The Sørensen–Dice coefficient of the character bigrams of two texts, from `0.0` to `1.0`. Works
+/// for languages without spaces between words, unlike a coefficient over words.
+fn similarity(a: &str, b: &str) -> f64 {
+    fn bigrams(text: &str) -> HashMap<(char, char), usize> {
+        let chars = text.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+        let mut bigrams = HashMap::new();
+        chars
+            .windows(2)
+            .for_each(|w| *bigrams.entry((w[0], w[1])).or_default() += 1);
+        bigrams
+    }
+
+    let (a, b) = (bigrams(a), bigrams(b));
+    let total = a.values().sum::<usize>() + b.values().sum::<usize>();
+    if total == 0 {
+        return 1.0;
+    }
+    let common = a.iter().map(|(k, n)| *n.min(b.get(k).unwrap_or(&0))).sum::<usize>();
+    2.0 * common as f64 / total as f64
+}
+
+/// The indices of the new sections in the longest run of matches which keeps the order of the old
+/// sections. The other matched sections moved.
+fn longest_increasing_subsequence(matches: &[Option<usize>]) -> Vec<usize> {
+    let items = matches
+        .iter()
+        .enumerate()
+        .filter_map(|(j, i)| i.map(|i| (j, i)))
+        .collect::<Vec<_>>();
+
+    // `tails[k]` is the index in `items` of the smallest tail of an increasing run of length k + 1.
+    let mut tails: Vec<usize> = vec![];
+    let mut previous = vec![None; items.len()];
+    for (n, &(_, i)) in items.iter().enumerate() {
+        let k = tails.partition_point(|&t| items[t].1 < i);
+        previous[n] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(n);
+        } else {
+            tails[k] = n;
+        }
+    }
+
+    let mut run = vec![];
+    let mut current = tails.last().copied();
+    while let Some(n) = current {
+        run.push(items[n].0);
+        current = previous[n];
+    }
+    run.reverse();
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(diffs: &[SectionDiff]) -> Vec<String> {
+        diffs
+            .iter()
+            .map(|d| match d {
+                SectionDiff::Added(s) => format!("+ {}", s.path.join("/")),
+                SectionDiff::Removed(s) => format!("- {}", s.path.join("/")),
+                SectionDiff::Matched { old, new, modified, moved, renamed } => format!(
+                    "{}{}{} {} -> {}",
+                    if *modified { "M" } else { "." },
+                    if *moved { "V" } else { "." },
+                    if *renamed { "R" } else { "." },
+                    old.path.join("/"),
+                    new.path.join("/"),
+                ),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = "# Guide\n\n## Install\n\nRun the installer and wait until it completes.\n\n## Update\n\nRun the updater.\n\n## Old\n\nGone.\n\n## Usage\n\nUse it.\n";
+        let new = "# Guide\n\n## Usage\n\nUse it well.\n\n## Setup\n\nRun the installer and wait until it has completed.\n\n## Update\n\nRun the updater.\n\n## FAQ\n\nAsk.\n";
+
+        let diffs = diff(old, new, None, &SplitOptions::default()).unwrap();
+        assert_eq!(
+            summary(&diffs),
+            vec![
+                "... Guide -> Guide",
+                "MV. Guide/Usage -> Guide/Usage",
+                "M.R Guide/Install -> Guide/Setup",
+                "... Guide/Update -> Guide/Update",
+                "- Guide/Old",
+                "+ Guide/FAQ",
+            ]
+        );
+        let SectionDiff::Matched { old: o, new: n, .. } = &diffs[2] else { panic!() };
+        assert_eq!(&old[o.range.clone()], o.text);
+        assert_eq!(&new[n.range.clone()], n.text);
+        assert!(diffs[0].is_unchanged());
+        assert!(!diffs[2].is_unchanged());
+    }
+
+    #[test]
+    fn test_heading_lines() {
+        // Neither a setext underline nor a comment before the heading is part of the content.
+        let old = "Guide\n=====\n\nRead it.\n\n<!-- Usage -->\n## Usage\n\nUse it.\n";
+        let new = "Guide\n===\n\nRead it.\n\n<!-- How to use -->\n## Usage\n\nUse it.\n";
+        let options = SplitOptions { comments: true, ..Default::default() };
+        let diffs = diff(old, new, None, &options).unwrap();
+        assert!(diffs.iter().all(SectionDiff::is_unchanged), "{:?}", summary(&diffs));
+
+        // Nor is the indentation or the trailing whitespace of the heading.
+        let new = "Guide\n=====\n\nRead it.\n\n  ## Usage ##  \n\nUse it.\n";
+        let sections = split_sections_with(new, None, &options).unwrap();
+        assert_eq!(sections[1].heading_range, Some(25..36));
+        assert_eq!(body(&sections[1]), "\nUse it.\n");
+
+        let new = "Guide\n=====\n\nRead it twice.\n\n## Usage\n\nUse it.\n";
+        let diffs = diff(old, new, None, &options).unwrap();
+        assert_eq!(summary(&diffs), vec!["M.. Guide -> Guide", "... Guide/Usage -> Guide/Usage"]);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("abc", "abc"), 1.0);
+        assert_eq!(similarity("ab", "cd"), 0.0);
+        assert!(similarity("インストールする", "インストールした") > 0.7);
+    }
+}
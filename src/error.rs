@@ -0,0 +1,57 @@
+use crate::location::Location;
+
+/// An error which occurred while splitting a markdown text.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The markdown text is empty, and
+    /// [`SplitOptions::allow_empty`](crate::SplitOptions::allow_empty) is not set.
+    #[error("The input text is empty")]
+    EmptyInput,
+    /// The range of heading depths to split on is not within `1..=6`, or is empty.
+    #[error("Invalid heading depth range: {min}..={max}")]
+    InvalidDepthRange { min: u8, max: u8 },
+    /// The maximum size of chunks is zero.
+    #[error("The maximum chunk size must be greater than zero")]
+    InvalidChunkSize,
+    /// The pattern of a marker is not a valid regular expression.
+    #[error("Invalid marker pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
+    /// The `markdown` crate could not parse the text, e.g. because of invalid MDX.
+    #[error("{message}")]
+    Parse {
+        /// The message of the parser.
+        message: String,
+        /// Where in the text the parser failed, if known.
+        position: Option<Location>,
+    },
+    /// A stream of markdown text could not be read, or is not valid UTF-8.
+    #[error("Failed to read the input: {0}")]
+    Io(#[from] std::io::Error),
+    /// An option is set which the [`Backend`](crate::Backend) in use does not support.
+    #[error("The {backend:?} backend does not support the `{option}` option")]
+    Unsupported {
+        /// The backend in use.
+        backend: crate::Backend,
+        /// The name of the option.
+        option: &'static str,
+    },
+    /// A glob pattern of [`BatchOptions`](crate::BatchOptions) is invalid.
+    #[cfg(feature = "batch")]
+    #[error("Invalid glob pattern: {0}")]
+    InvalidGlob(#[from] globset::Error),
+    /// The `markdown` crate panicked while parsing the text.
+    #[error("The markdown parser panicked")]
+    ParserPanic,
+    /// The front matter is not valid YAML, or does not match the type it is deserialized into.
+    #[cfg(feature = "front-matter")]
+    #[error("Invalid YAML front matter: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    /// The front matter is not valid TOML, or does not match the type it is deserialized into.
+    #[cfg(feature = "front-matter")]
+    #[error("Invalid TOML front matter: {0}")]
+    Toml(#[from] toml::de::Error),
+}
+
+/// A `Result` with [`Error`] as its error type.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,99 @@
+use std::ops::Range;
+
+use markdown::mdast::{
+    Node,
+    Node::{Root, Toml, Yaml},
+};
+#[cfg(feature = "front-matter")]
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "front-matter")]
+use crate::error::Result;
+use crate::scan::Lines;
+
+/// The front matter at the very start of a markdown text, e.g. the YAML metadata of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FrontMatter<'a> {
+    /// The format of the front matter.
+    pub kind: FrontMatterKind,
+    /// The slice of the original markdown text covered by the front matter, including its fences
+    /// and the blank lines following it.
+    pub text: &'a str,
+    /// The content of the front matter, without its fences.
+    pub raw: &'a str,
+    /// The byte range of the front matter in the original markdown text, same as `text`.
+    pub range: Range<usize>,
+}
+
+/// The format of a front matter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FrontMatterKind {
+    /// YAML, fenced with `---`.
+    Yaml,
+    /// TOML, fenced with `+++`.
+    Toml,
+}
+
+impl<'a> FrontMatter<'a> {
+    /// The front matter of a text given the range of its fences and content, extended over the
+    /// line breaks following it.
+    pub(crate) fn new(text: &'a str, kind: FrontMatterKind, fenced: Range<usize>) -> Self {
+        let raw = &text[fenced.clone()];
+        let raw = match (raw.find('\n'), raw.rfind('\n')) {
+            (Some(first), Some(last)) if first < last => {
+                let raw = &raw[first + 1..last];
+                raw.strip_suffix('\r').unwrap_or(raw)
+            }
+            _ => "",
+        };
+        let rest = &text[fenced.end..];
+        let end = fenced.end + (rest.len() - rest.trim_start_matches(['\r', '\n']).len());
+
+        let range = fenced.start..end;
+        FrontMatter { kind, text: &text[range.clone()], raw, range }
+    }
+
+    /// Deserializes the content of the front matter, e.g. into your own struct or into a generic
+    /// value such as `serde_yaml::Value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content is not valid YAML or TOML, or does not match `T`.
+    #[cfg(feature = "front-matter")]
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(match self.kind {
+            FrontMatterKind::Yaml => serde_yaml::from_str(self.raw)?,
+            FrontMatterKind::Toml => toml::from_str(self.raw)?,
+        })
+    }
+}
+
+/// Find the front matter fenced with `---` or `+++` at the very start of a markdown text without
+/// parsing it, where the `markdown` crate would find it.
+pub(crate) fn scan_front_matter(text: &str) -> Option<FrontMatter<'_>> {
+    let mut lines = Lines::new(text);
+    let (_, first) = lines.next()?;
+    let (kind, fence) = match first.trim_end_matches([' ', '\t']) {
+        "---" => (FrontMatterKind::Yaml, "---"),
+        "+++" => (FrontMatterKind::Toml, "+++"),
+        _ => return None,
+    };
+    lines
+        .find(|(_, line)| line.trim_end_matches([' ', '\t']) == fence)
+        .map(|(offset, line)| FrontMatter::new(text, kind, 0..offset + line.len()))
+}
+
+/// Find the front matter of a markdown text in its AST, which is only there if the parser was told
+/// to look for it.
+pub(crate) fn find_front_matter<'a>(text: &'a str, ast: &Node) -> Option<FrontMatter<'a>> {
+    let Root(root) = ast else { return None };
+    let (kind, position) = match root.children.first()? {
+        Yaml(yaml) => (FrontMatterKind::Yaml, yaml.position.as_ref()?),
+        Toml(toml) => (FrontMatterKind::Toml, toml.position.as_ref()?),
+        _ => return None,
+    };
+
+    Some(FrontMatter::new(text, kind, position.start.offset..position.end.offset))
+}
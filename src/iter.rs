@@ -0,0 +1,258 @@
+use std::iter::FusedIterator;
+
+use markdown::{mdast::Node, ParseOptions};
+
+use crate::{
+    error::Result,
+    location::LineIndex,
+    options::{Backend, SplitOptions},
+    references::{Kind, References},
+    section::Section,
+    source,
+    split::{parse, split_points, SplitPoint},
+};
+
+/// Split a markdown text into [`Section`]s lazily, as configured by `split_options`
+///
+/// The text is parsed up front, but each section is only built once it is yielded, so that taking
+/// the first section, skipping to the n-th one or iterating from the end does not build every
+/// section. See [`split_sections`](crate::split_sections) for the arguments.
+///
+/// # Returns
+///
+/// A [`SectionIter`] over the sections of the text.
+///
+/// # Errors
+///
+/// Returns an error if the markdown text is empty, if the depth range in `split_options` is
+/// invalid, or if the text cannot be parsed by the `markdown` crate.
+///
+/// ```
+/// use markdown_split::{split_iter, SplitOptions};
+///
+/// let text = "# Install\n\nRun it.\n\n# Update\n\nRun it again.\n\n# Uninstall\n";
+/// let mut sections = split_iter(text, None, &SplitOptions::default())?;
+/// assert_eq!(sections.len(), 3);
+/// assert_eq!(sections.next_back().unwrap().heading.as_deref(), Some("Uninstall"));
+/// assert_eq!(sections.nth(1).unwrap().heading.as_deref(), Some("Update"));
+/// # Ok::<(), markdown_split::Error>(())
+/// ```
+pub fn split_iter<'a>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+) -> Result<SectionIter<'a>> {
+    match split_options.backend {
+        Backend::Mdast => {
+            let ast = parse(text, options, split_options)?;
+            Ok(SectionIter::new(text, &ast, split_options))
+        }
+        backend => {
+            let (_, points) = source::split_points(text, options, split_options, backend)?;
+            Ok(SectionIter::from_points(text, points, References::none(text)))
+        }
+    }
+}
+
+/// An iterator over the sections of a markdown text, which builds each section as it is yielded.
+#[derive(Debug, Clone)]
+pub struct SectionIter<'a> {
+    text: &'a str,
+    /// Where each section starts, followed by the end of the text.
+    points: Vec<SplitPoint>,
+    /// For each section, the innermost section whose heading encloses it, other than itself.
+    parents: Vec<Option<usize>>,
+    lines: LineIndex<'a>,
+    references: References<'a>,
+    /// The index of the next section to yield from the front.
+    front: usize,
+    /// The index after the next section to yield from the back.
+    back: usize,
+}
+
+impl<'a> SectionIter<'a> {
+    pub(crate) fn new(text: &'a str, ast: &Node, split_options: &SplitOptions) -> Self {
+        let kinds =
+            [(split_options.definitions, Kind::Link), (split_options.footnotes, Kind::Footnote)]
+                .into_iter()
+                .filter_map(|(enabled, kind)| enabled.then_some(kind))
+                .collect();
+        let references = References::new(text, ast, kinds);
+        Self::from_points(text, split_points(text, ast, split_options), references)
+    }
+
+    /// Iterate over the sections between split points, the last of which is the end of the text.
+    pub(crate) fn from_points(
+        text: &'a str,
+        points: Vec<SplitPoint>,
+        references: References<'a>,
+    ) -> Self {
+        // The sections whose headings enclose the current one.
+        let mut ancestors: Vec<usize> = vec![];
+        let mut parents = vec![];
+        for (i, point) in points.iter().enumerate() {
+            match (point.depth, &point.heading) {
+                (Some(depth), Some(_)) => {
+                    while ancestors.last().is_some_and(|&j| points[j].depth >= Some(depth)) {
+                        ancestors.pop();
+                    }
+                    parents.push(ancestors.last().copied());
+                    ancestors.push(i);
+                }
+                _ => parents.push(ancestors.last().copied()),
+            }
+        }
+
+        Self {
+            text,
+            back: points.len().saturating_sub(1),
+            points,
+            parents,
+            lines: LineIndex::new(text),
+            references,
+            front: 0,
+        }
+    }
+
+    /// The section containing a byte offset of the text, whether it was yielded already or not.
+    /// The end of the text belongs to the last section.
+    pub fn at_offset(&self, offset: usize) -> Option<Section<'a>> {
+        let i = self.points.partition_point(|p| p.offset <= offset).checked_sub(1)?;
+        if i + 1 < self.points.len() {
+            Some(self.section(i))
+        } else if i > 0 && offset == self.text.len() {
+            Some(self.section(i - 1))
+        } else {
+            None
+        }
+    }
+
+    /// Build the `i`-th section.
+    fn section(&self, i: usize) -> Section<'a> {
+        let (start, end) = (&self.points[i], &self.points[i + 1]);
+
+        let mut path = vec![];
+        let mut current = match start.heading {
+            Some(_) => Some(i),
+            None => self.parents[i],
+        };
+        while let Some(j) = current {
+            path.extend(self.points[j].heading.clone());
+            current = self.parents[j];
+        }
+        path.reverse();
+
+        let mut section = Section {
+            text: &self.text[start.offset..end.offset],
+            depth: start.depth,
+            heading: start.heading.clone(),
+            id: start.id.clone(),
+            slug: start.slug.clone(),
+            heading_range: start.heading_range.clone(),
+            path,
+            definitions: vec![],
+            footnotes: vec![],
+            range: start.offset..end.offset,
+            start: self.lines.location(start.offset),
+            end: self.lines.location(end.offset),
+        };
+        self.references.attach(&mut section);
+        section
+    }
+}
+
+impl<'a> Iterator for SectionIter<'a> {
+    type Item = Section<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.section(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.back.min(self.front.saturating_add(n));
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for SectionIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.section(self.back))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.back = self.front.max(self.back.saturating_sub(n));
+        self.next_back()
+    }
+}
+
+impl ExactSizeIterator for SectionIter<'_> {}
+
+impl FusedIterator for SectionIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+    use crate::split::split_sections_with;
+
+    #[test]
+    fn test_iter() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.en.md").unwrap();
+        let options = SplitOptions { definitions: true, ..Default::default() };
+        let sections = split_sections_with(&text, None, &options).unwrap();
+
+        let iter = split_iter(&text, None, &options).unwrap();
+        assert_eq!(iter.len(), 7);
+        assert_eq!(
+            iter.clone().rev().collect::<Vec<_>>(),
+            sections.iter().cloned().rev().collect::<Vec<_>>()
+        );
+
+        let mut iter = iter.skip(2);
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(sections[2].clone()));
+        let mut iter = split_iter(&text, None, &options).unwrap();
+        assert_eq!(iter.nth_back(1), Some(sections[5].clone()));
+        assert_eq!(iter.nth(4), Some(sections[4].clone()));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.nth(10), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(iter.at_offset(0), Some(sections[0].clone()));
+        assert_eq!(iter.at_offset(sections[3].range.start), Some(sections[3].clone()));
+        assert_eq!(iter.at_offset(text.len()), Some(sections[6].clone()));
+        assert_eq!(iter.at_offset(text.len() + 1), None);
+    }
+
+    #[test]
+    fn test_path() {
+        let text = "# A\n\n## B\n\n---\n\nC\n\n### D\n\n## E\n";
+        let options = SplitOptions {
+            markers: vec![crate::Marker::ThematicBreak],
+            ..Default::default()
+        };
+        let paths = split_iter(text, None, &options)
+            .unwrap()
+            .rev()
+            .map(|s| s.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![vec!["A", "E"], vec!["A", "B", "D"], vec!["A", "B"], vec!["A", "B"], vec!["A"]]
+        );
+    }
+}
@@ -0,0 +1,143 @@
+/// Join sections back into a markdown text
+///
+/// This is the inverse of [`split`](crate::split): joining the sections of a text, unmodified,
+/// reproduces it exactly. Sections may also be edited in between, e.g. translated one by one, in
+/// which case a line break is added after any section which does not end with one and is followed
+/// by a non-empty section, so that the heading of the following section still starts a line. To
+/// keep the front matter extracted by [`split_document`](crate::split_document), pass its text as
+/// the first section.
+///
+/// # Arguments
+///
+/// - `sections`: The sections to join, in order, either borrowed or owned.
+///
+/// # Returns
+///
+/// The joined markdown text.
+pub fn join<I, S>(sections: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut sections = sections.into_iter().peekable();
+    let mut text = String::new();
+    while let Some(section) = sections.next() {
+        let section = section.as_ref();
+        text.push_str(section);
+        let next = sections.peek().map(|s| s.as_ref());
+        if next.is_some_and(|s| !s.is_empty()) && !section.is_empty() && !section.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fs::read_to_string;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        options::{Marker, SplitOptions},
+        section::{Document, Section},
+        split::{split, split_document},
+    };
+
+    #[test]
+    fn test_fixtures() {
+        for path in [
+            "tests/fixtures/ch01-01-installation.en.md",
+            "tests/fixtures/ch01-01-installation.ja.md",
+        ] {
+            let text = read_to_string(path).unwrap();
+            assert_eq!(join(split(&text, None).unwrap()), text);
+        }
+    }
+
+    #[test]
+    fn test_edited() {
+        let text = "# A\n\nHello\n\n# B\n\nWorld";
+
+        let sections = split(text, None)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.trim_end().replace("Hello", "Bonjour"))
+            .collect::<Vec<_>>();
+        assert_eq!(join(&sections), "# A\n\nBonjour\n# B\n\nWorld");
+        assert_eq!(join(Vec::<String>::new()), "");
+    }
+
+    /// Lines of markdown which are likely to interact with how the text is split.
+    pub(crate) fn markdown() -> impl Strategy<Value = String> {
+        let line = prop_oneof![
+            "#{1,7} [a-zA-Z あ]{0,8}",
+            "[a-zA-Z .!?。]{0,16}",
+            Just("```".to_string()),
+            Just("~~~rust".to_string()),
+            Just("---".to_string()),
+            Just("===".to_string()),
+            Just("+++".to_string()),
+            Just("<!-- split -->".to_string()),
+            Just("<!-- ## Original -->".to_string()),
+            Just("<div>".to_string()),
+            "(> |- |1\\. |    |\t)#{0,2} [a-z]{0,8}",
+            "\\[[a-z]{1,3}\\]: https://example\\.com",
+            "\\[\\^[0-9]\\]:? [a-z]{0,8}",
+            any::<String>(),
+            Just(String::new()),
+        ];
+        (prop::collection::vec(line, 1..24), any::<bool>())
+            .prop_map(|(lines, newline)| lines.join(if newline { "\n" } else { "\r\n" }))
+    }
+
+    /// Split a text into its front matter and its sections, or `None` if the `markdown` crate
+    /// cannot parse it, see `split::tests::test_parser_panic`, so that property tests skip it.
+    pub(crate) fn parsed<'a>(text: &'a str, options: &SplitOptions) -> Option<Document<'a>> {
+        split_document(text, None, options).ok()
+    }
+
+    /// Split a text both with the AST, unless [`parsed`] skips it, and another way, e.g. with
+    /// another backend or as a stream.
+    ///
+    /// Compare the boundaries of the sections split both ways rather than their text in property
+    /// tests: the text of a heading may differ, as a reference defined elsewhere in the text turns
+    /// `[a]` into a link.
+    pub(crate) fn both<'a, T>(
+        text: &'a str,
+        options: &SplitOptions,
+        other: impl FnOnce(&'a str, &SplitOptions) -> T,
+    ) -> Option<(Vec<Section<'a>>, T)> {
+        let document = parsed(text, options)?;
+        Some((document.sections, other(text, options)))
+    }
+
+    proptest! {
+        #[test]
+        fn test_split_join(text in markdown()) {
+            if let Some(document) = parsed(&text, &SplitOptions::default()) {
+                prop_assert_eq!(join(document.sections), text.as_str());
+            }
+
+            let options = SplitOptions {
+                min_depth: 2,
+                max_depth: 3,
+                markers: vec![Marker::ThematicBreak, Marker::html_comment("split").unwrap()],
+                comments: true,
+                nested: true,
+                ..Default::default()
+            };
+            if let Some(document) = parsed(&text, &options) {
+                prop_assert_eq!(join(document.sections), text.as_str());
+            }
+
+            let options = SplitOptions { front_matter: true, ..Default::default() };
+            if let Some(document) = parsed(&text, &options) {
+                let sections = document.front_matter.iter().map(|f| f.text);
+                let sections = sections.chain(document.sections.iter().map(|s| s.text));
+                prop_assert_eq!(join(sections), text.as_str());
+            }
+        }
+    }
+}
@@ -3,5 +3,48 @@
 //! This crate provides a function to split a markdown text into sections based on headings. It is
 //! useful for splitting a markdown text into smaller parts for further processing. The sections are
 //! determined by the headings in the markdown text (h1-h6).
-pub use split::split;
+pub use align::{align, AlignedPair, Alignment};
+#[cfg(feature = "batch")]
+pub use batch::{
+    split_dir, Batch, BatchOptions, FileDocument, FileError, FileFrontMatter, FileSection,
+};
+pub use chunk::{chunk, ChunkOptions};
+pub use diff::{diff, SectionDiff};
+pub use error::{Error, Result};
+pub use front_matter::{FrontMatter, FrontMatterKind};
+pub use iter::{split_iter, SectionIter};
+pub use join::join;
+pub use location::{section_at, Location, Position};
+pub use options::{Backend, Marker, SplitOptions};
+pub use section::{Document, Section};
+pub use sizer::{Bytes, Chars, Graphemes, Sizer, Words};
+pub use slug::slug;
+pub use split::{split, split_document, split_sections, split_sections_with, split_with};
+#[cfg(feature = "tokio")]
+pub use stream::{split_async_reader, AsyncSectionReader};
+pub use stream::{split_reader, OwnedSection, SectionReader};
+pub use tree::{split_tree, split_tree_with, SectionNode};
+mod align;
+#[cfg(feature = "batch")]
+mod batch;
+mod chunk;
+#[cfg(feature = "comrak")]
+mod comrak;
+mod diff;
+mod error;
+mod front_matter;
+mod iter;
+mod join;
+mod location;
+mod options;
+#[cfg(feature = "pulldown-cmark")]
+mod pulldown;
+mod references;
+mod scan;
+mod section;
+mod sizer;
+mod slug;
+mod source;
 mod split;
+mod stream;
+mod tree;
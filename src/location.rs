@@ -0,0 +1,169 @@
+use crate::section::Section;
+
+/// A location in a markdown text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Location {
+    /// 1-indexed line number.
+    pub line: usize,
+    /// 1-indexed column number, counted in characters.
+    pub column: usize,
+    /// 1-indexed column number, counted in UTF-8 bytes.
+    pub utf8_column: usize,
+    /// 1-indexed column number, counted in UTF-16 code units. The Language Server Protocol counts
+    /// both lines and characters from 0 instead.
+    pub utf16_column: usize,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self {
+            line: 1,
+            column: 1,
+            utf8_column: 1,
+            utf16_column: 1,
+        }
+    }
+}
+
+/// A cursor position in a markdown text, as reported by an editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Position {
+    /// A byte offset.
+    Offset(usize),
+    /// A 1-indexed line, and a 1-indexed column counted in UTF-8 bytes.
+    Utf8 { line: usize, column: usize },
+    /// A 1-indexed line, and a 1-indexed column counted in UTF-16 code units. Add 1 to both the
+    /// line and the character of a position of the Language Server Protocol.
+    Utf16 { line: usize, column: usize },
+}
+
+impl Position {
+    /// The position as a pair which orders like positions in the text.
+    fn key(&self) -> (usize, usize) {
+        match *self {
+            Position::Offset(offset) => (offset, 0),
+            Position::Utf8 { line, column } | Position::Utf16 { line, column } => (line, column),
+        }
+    }
+
+    /// A location in the text as a pair comparable with [`Position::key`].
+    fn key_at(&self, offset: usize, location: &Location) -> (usize, usize) {
+        match self {
+            Position::Offset(_) => (offset, 0),
+            Position::Utf8 { .. } => (location.line, location.utf8_column),
+            Position::Utf16 { .. } => (location.line, location.utf16_column),
+        }
+    }
+}
+
+/// Find the section containing a cursor position, e.g. to report a diagnostic per section
+///
+/// # Arguments
+///
+/// - `sections`: The sections of a markdown text, in order, as returned by
+///   [`split_sections`](crate::split_sections).
+/// - `position`: The cursor position, as a byte offset or as a line and a column.
+///
+/// # Returns
+///
+/// The section containing the position, or `None` if the position is before the first section,
+/// e.g. in the front matter, or after the end of the text. A position at the very end of the text
+/// belongs to the last section.
+///
+/// ```
+/// use markdown_split::{section_at, split_sections, Position};
+///
+/// let sections = split_sections("# Install\n\nRun it.\n\n# Update\n\nRun it again.\n", None)?;
+/// let section = section_at(&sections, Position::Utf16 { line: 7, column: 5 }).unwrap();
+/// assert_eq!(section.heading.as_deref(), Some("Update"));
+/// # Ok::<(), markdown_split::Error>(())
+/// ```
+pub fn section_at<'s, 'a>(
+    sections: &'s [Section<'a>],
+    position: Position,
+) -> Option<&'s Section<'a>> {
+    let key = position.key();
+    let index = sections.partition_point(|s| position.key_at(s.range.start, &s.start) <= key);
+    let section = sections.get(index.checked_sub(1)?)?;
+    let end = position.key_at(section.range.end, &section.end);
+    (key < end || (index == sections.len() && key == end)).then_some(section)
+}
+
+/// The start offsets of the lines of a text, to find the location of any offset.
+#[derive(Debug, Clone)]
+pub(crate) struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let starts = [0].into_iter().chain(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { text, starts: starts.collect() }
+    }
+
+    /// The location of a byte offset, which must be on a character boundary.
+    pub(crate) fn location(&self, offset: usize) -> Location {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let before = &self.text[self.starts[line - 1]..offset];
+        Location {
+            line,
+            column: before.chars().count() + 1,
+            utf8_column: before.len() + 1,
+            utf16_column: before.encode_utf16().count() + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split::split_sections;
+
+    #[test]
+    fn test_location() {
+        let index = LineIndex::new("# 🦀 Rust\r\n\nあい😀x");
+        assert_eq!(index.location(0), Location::default());
+        assert_eq!(
+            index.location(11),
+            Location {
+                line: 1,
+                column: 9,
+                utf8_column: 12,
+                utf16_column: 10
+            }
+        );
+        assert_eq!(index.location(14), Location { line: 3, ..Default::default() });
+        assert_eq!(
+            index.location(24),
+            Location {
+                line: 3,
+                column: 4,
+                utf8_column: 11,
+                utf16_column: 5
+            }
+        );
+    }
+
+    #[test]
+    fn test_section_at() {
+        let text = "Intro\n\n## 🦀 A\n\nText\n\n## B\n";
+        let sections = split_sections(text, None).unwrap();
+        assert_eq!(sections[1].start, Location { line: 3, ..Default::default() });
+        assert_eq!(sections[1].end, Location { line: 7, ..Default::default() });
+        assert_eq!(sections[2].end, Location { line: 8, ..Default::default() });
+
+        let heading = |position| section_at(&sections, position).map(|s| s.heading.as_deref());
+        assert_eq!(heading(Position::Offset(0)), Some(None));
+        assert_eq!(heading(Position::Offset(7)), Some(Some("🦀 A")));
+        assert_eq!(heading(Position::Offset(text.len())), Some(Some("B")));
+        assert_eq!(heading(Position::Offset(text.len() + 1)), None);
+        assert_eq!(heading(Position::Utf8 { line: 2, column: 1 }), Some(None));
+        assert_eq!(heading(Position::Utf8 { line: 3, column: 8 }), Some(Some("🦀 A")));
+        assert_eq!(heading(Position::Utf16 { line: 6, column: 80 }), Some(Some("🦀 A")));
+        assert_eq!(heading(Position::Utf16 { line: 7, column: 1 }), Some(Some("B")));
+        assert_eq!(heading(Position::Utf16 { line: 9, column: 1 }), None);
+        assert_eq!(section_at(&[], Position::Offset(0)), None);
+    }
+}
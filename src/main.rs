@@ -0,0 +1,414 @@
+use std::{
+    collections::HashSet,
+    fs::{create_dir_all, read_to_string, write},
+    io::{stdin, stdout, Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use markdown_split::{
+    slug, split_dir, split_sections_with, BatchOptions, FileDocument, FileSection, Location,
+    Section, SplitOptions,
+};
+use serde::Serialize;
+
+/// Split a markdown file into one file per section.
+#[derive(Debug, Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The markdown file to split. Reads from stdin if omitted or `-`.
+    input: Option<PathBuf>,
+
+    #[command(flatten)]
+    output: Output,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Split every markdown file of a directory tree, reporting the files which cannot be split
+    /// without stopping.
+    Batch(BatchArgs),
+}
+
+/// Split every markdown file of a directory tree.
+#[derive(Debug, ClapArgs)]
+struct BatchArgs {
+    /// The directory to walk.
+    dir: PathBuf,
+
+    /// A glob pattern of the files to split, relative to the directory. May be repeated.
+    #[arg(long, default_value = "**/*.md")]
+    include: Vec<String>,
+
+    /// A glob pattern of the files and directories to skip, relative to the directory. May be
+    /// repeated.
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    #[command(flatten)]
+    output: Output,
+}
+
+/// How to split the sections and where to output them.
+#[derive(Debug, ClapArgs)]
+struct Output {
+    /// How to output the sections.
+    #[arg(short, long, value_enum, default_value_t = Format::Files)]
+    format: Format,
+
+    /// The directory to write the sections to, with `--format files`. Created if it does not
+    /// exist. With `batch`, the sections of each file go to a directory named after the file.
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+
+    /// The shallowest heading depth to split on.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=6))]
+    min_depth: u8,
+
+    /// The deepest heading depth to split on.
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u8).range(1..=6))]
+    max_depth: u8,
+
+    /// The template of the file names. `{index}` is replaced with the zero-padded number of the
+    /// section, `{slug}` with its heading slugged, and `{depth}` with its heading depth.
+    #[arg(short, long, default_value = "{index}-{slug}.md")]
+    name: String,
+
+    /// Overwrite the files which already exist, with `--format files`. Without it, nothing is
+    /// written if any file exists.
+    #[arg(long)]
+    force: bool,
+}
+
+impl Output {
+    /// The options to split on the headings within the depth range.
+    fn split_options(&self) -> SplitOptions {
+        SplitOptions {
+            min_depth: self.min_depth,
+            max_depth: self.max_depth,
+            ..Default::default()
+        }
+    }
+}
+
+/// How to output the sections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// One file per section in the output directory, printing the path of each file.
+    Files,
+    /// A single JSON array of sections to stdout.
+    Json,
+    /// One JSON object per section and per line to stdout.
+    Jsonl,
+}
+
+/// A section as output in JSON.
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    /// The 0-indexed position of the section in the document.
+    index: usize,
+    #[serde(flatten)]
+    section: &'a Section<'a>,
+    /// The 1-indexed lines covered by the section, end exclusive.
+    lines: Range<usize>,
+}
+
+impl<'a> Record<'a> {
+    fn new(index: usize, section: &'a Section<'a>) -> Self {
+        Self {
+            index,
+            section,
+            lines: lines(section.start, section.end),
+        }
+    }
+}
+
+/// A section of a file split with `batch`, as output in JSON: a [`Record`] along with the path of
+/// the file.
+#[derive(Debug, Serialize)]
+struct FileRecord<'a> {
+    /// The path of the file, which starts with the walked directory.
+    file: &'a Path,
+    /// The 0-indexed position of the section in the file.
+    index: usize,
+    #[serde(flatten)]
+    section: &'a FileSection,
+    /// The 1-indexed lines covered by the section, end exclusive.
+    lines: Range<usize>,
+}
+
+impl<'a> FileRecord<'a> {
+    fn new(file: &'a Path, index: usize, section: &'a FileSection) -> Self {
+        Self {
+            file,
+            index,
+            section,
+            lines: lines(section.start, section.end),
+        }
+    }
+
+    /// The records of the sections of every file, in order.
+    fn all(files: &'a [FileDocument]) -> impl Iterator<Item = Self> {
+        files.iter().flat_map(|file| {
+            let sections = file.sections.iter().enumerate();
+            sections.map(|(index, section)| Self::new(&file.path, index, section))
+        })
+    }
+}
+
+/// The 1-indexed lines covered by a section, end exclusive.
+fn lines(start: Location, end: Location) -> Range<usize> {
+    // The end of a section is exclusive, in the middle of its last line if it has no line break.
+    start.line..end.line + usize::from(end.column > 1)
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    match &args.command {
+        Some(Command::Batch(batch)) => split_batch(batch),
+        None => split_file(&args),
+    }
+}
+
+/// Split a single file, or stdin.
+fn split_file(args: &Args) -> Result<()> {
+    let text = match &args.input {
+        Some(path) if path.as_os_str() != "-" => {
+            read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?
+        }
+        _ => {
+            let mut text = String::new();
+            stdin().read_to_string(&mut text).context("Failed to read stdin")?;
+            text
+        }
+    };
+
+    let sections = split_sections_with(&text, None, &args.output.split_options())?;
+
+    match args.output.format {
+        Format::Files => {
+            let sections = sections
+                .iter()
+                .map(|s| (s.text, s.heading.as_deref(), s.depth))
+                .collect::<Vec<_>>();
+            write_files(
+                &args.output,
+                &section_files(&args.output, &args.output.output, &sections),
+            )?;
+        }
+        Format::Json => {
+            let records = sections.iter().enumerate().map(|(i, s)| Record::new(i, s));
+            serde_json::to_writer_pretty(stdout().lock(), &records.collect::<Vec<_>>())?;
+            println!();
+        }
+        Format::Jsonl => {
+            let mut stdout = stdout().lock();
+            for (index, section) in sections.iter().enumerate() {
+                serde_json::to_writer(&mut stdout, &Record::new(index, section))?;
+                writeln!(stdout)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Split every markdown file of a directory tree, then report the files which could not be split.
+fn split_batch(batch: &BatchArgs) -> Result<()> {
+    let options = BatchOptions {
+        include: batch.include.clone(),
+        exclude: batch.exclude.clone(),
+        split: batch.output.split_options(),
+    };
+    let result = split_dir(&batch.dir, None, &options)?;
+
+    match batch.output.format {
+        Format::Files => {
+            let mut files = vec![];
+            for file in &result.files {
+                // `a/b.md` is written to `<output>/a/b/`.
+                let relative = file.path.strip_prefix(&batch.dir).unwrap_or(&file.path);
+                let dir = batch.output.output.join(relative.with_extension(""));
+                let sections = file
+                    .sections
+                    .iter()
+                    .map(|s| (s.text.as_str(), s.heading.as_deref(), s.depth))
+                    .collect::<Vec<_>>();
+                files.extend(section_files(&batch.output, &dir, &sections));
+            }
+            write_files(&batch.output, &files)?;
+        }
+        Format::Json => {
+            let records = FileRecord::all(&result.files);
+            serde_json::to_writer_pretty(stdout().lock(), &records.collect::<Vec<_>>())?;
+            println!();
+        }
+        Format::Jsonl => {
+            let mut stdout = stdout().lock();
+            for record in FileRecord::all(&result.files) {
+                serde_json::to_writer(&mut stdout, &record)?;
+                writeln!(stdout)?;
+            }
+        }
+    }
+
+    for error in &result.errors {
+        eprintln!("{}: {}", error.path.display(), error.error);
+    }
+    if !result.errors.is_empty() {
+        bail!("Failed to split {} of the files", result.errors.len());
+    }
+
+    Ok(())
+}
+
+/// The file in `dir` to write the text of each section to, given along with its heading and depth.
+fn section_files<'a>(
+    output: &Output,
+    dir: &Path,
+    sections: &[(&'a str, Option<&str>, Option<u8>)],
+) -> Vec<(PathBuf, &'a str)> {
+    let width = sections.len().to_string().len();
+    sections
+        .iter()
+        .enumerate()
+        .map(|(index, (text, heading, depth))| {
+            (dir.join(file_name(&output.name, index, width, *heading, *depth)), *text)
+        })
+        .collect()
+}
+
+/// Write each text to its file, creating the directories. Nothing is written if two texts would be
+/// written to the same file, or if a file exists without `--force`.
+fn write_files(output: &Output, files: &[(PathBuf, &str)]) -> Result<()> {
+    let mut paths = HashSet::new();
+    for (path, _) in files {
+        if !paths.insert(path) {
+            bail!(
+                "Several sections would be written to {}, add `{{index}}` to the file name",
+                path.display()
+            );
+        }
+        if !output.force && path.exists() {
+            bail!("{} already exists, pass `--force` to overwrite it", path.display());
+        }
+    }
+
+    for (path, text) in files {
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        write(path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Render the file name of a section from a template.
+fn file_name(
+    template: &str,
+    index: usize,
+    width: usize,
+    heading: Option<&str>,
+    depth: Option<u8>,
+) -> String {
+    // The text before the first heading, or a heading made only of punctuation, has no slug.
+    let slug = match slug(heading.unwrap_or_default()) {
+        slug if slug.is_empty() => "untitled".to_string(),
+        slug => slug,
+    };
+    template
+        .replace("{index}", &format!("{index:0width$}"))
+        .replace("{slug}", &slug)
+        .replace("{depth}", &depth.unwrap_or(0).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use markdown_split::split_sections;
+
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        let text = "Intro\n\n## Installing `rustup` on Linux/macOS\n\n### インストール\n";
+        let sections = split_sections(text, None).unwrap();
+
+        let names = sections
+            .iter()
+            .enumerate()
+            .map(|(i, s)| file_name("{index}-{slug}.md", i, 2, s.heading.as_deref(), s.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["00-untitled.md", "01-installing-rustup-on-linuxmacos.md", "02-インストール.md"]
+        );
+        assert_eq!(file_name("h{depth}_{index}.txt", 1, 1, Some("A"), Some(2)), "h2_1.txt");
+        assert_eq!(file_name("{slug}.md", 0, 1, Some("?!"), Some(1)), "untitled.md");
+    }
+
+    #[test]
+    fn test_write_files() {
+        let dir = std::env::temp_dir().join(format!("markdown-split-cli-{}", std::process::id()));
+        let mut output =
+            Args::parse_from(["markdown-split", "--output", dir.to_str().unwrap()]).output;
+        let sections = [("# A\n", Some("A"), Some(1)), ("# B\n", Some("B"), Some(1))];
+        let files = section_files(&output, &dir, &sections);
+        write_files(&output, &files).unwrap();
+        assert_eq!(read_to_string(dir.join("0-a.md")).unwrap(), "# A\n");
+
+        // Existing files are only overwritten with `--force`.
+        let files = [(dir.join("1-b.md"), "# C\n")];
+        assert!(write_files(&output, &files).is_err());
+        assert_eq!(read_to_string(dir.join("1-b.md")).unwrap(), "# B\n");
+        output.force = true;
+        write_files(&output, &files).unwrap();
+        assert_eq!(read_to_string(dir.join("1-b.md")).unwrap(), "# C\n");
+
+        // Sections sharing a file name are never written.
+        output.name = "{depth}.md".to_string();
+        let files = section_files(&output, &dir, &sections);
+        assert!(write_files(&output, &files).is_err());
+        assert!(!dir.join("1.md").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_record() {
+        let text = "Intro\n\n## A\n\nLast line";
+        let sections = split_sections(text, None).unwrap();
+
+        let record = serde_json::to_value(Record::new(1, &sections[1])).unwrap();
+        assert_eq!(record["index"], 1);
+        assert_eq!(record["heading"], "A");
+        assert_eq!(record["depth"], 2);
+        assert_eq!(record["text"], "## A\n\nLast line");
+        assert_eq!(record["range"], serde_json::json!({ "start": 7, "end": 22 }));
+        assert_eq!(record["lines"], serde_json::json!({ "start": 3, "end": 6 }));
+        assert_eq!(Record::new(0, &sections[0]).lines, 1..3);
+    }
+
+    #[test]
+    fn test_file_record() {
+        let text = "Intro\n\n## A {#a}\n\nLast line";
+        let sections = split_sections(text, None).unwrap();
+        let section = FileSection::from(sections[1].clone());
+
+        let record = serde_json::to_value(FileRecord::new(Path::new("docs/a.md"), 1, &section));
+        let mut record = record.unwrap();
+        assert_eq!(record["file"], "docs/a.md");
+        assert_eq!(record["path"], serde_json::json!(["A"]));
+        assert_eq!(record["id"], "a");
+
+        // The same schema as a single file, along with the path of the file.
+        record.as_object_mut().unwrap().remove("file");
+        assert_eq!(record, serde_json::to_value(Record::new(1, &sections[1])).unwrap());
+    }
+}
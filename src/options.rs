@@ -0,0 +1,160 @@
+use std::{fmt, sync::Arc};
+
+use markdown::mdast::{Node, Node::ThematicBreak};
+use regex::Regex;
+
+use crate::{error::Result, split::html_comment};
+
+/// Options to configure how a markdown text is split into sections, independent of how it is
+/// parsed.
+#[derive(Debug, Clone)]
+pub struct SplitOptions {
+    /// Whether headings start a new section. Turn this off to split only on `markers`.
+    pub headings: bool,
+    /// The shallowest heading depth to split on, from `1` (h1) to `6` (h6). Shallower headings
+    /// stay inside the preceding section.
+    pub min_depth: u8,
+    /// The deepest heading depth to split on, from `1` (h1) to `6` (h6). Deeper headings stay
+    /// inside their parent section.
+    pub max_depth: u8,
+    /// Whether to collect, for each section, the link reference definitions it uses which are
+    /// defined outside of it, so that the section can be rendered on its own. See
+    /// [`Section::definitions`](crate::Section::definitions).
+    pub definitions: bool,
+    /// Whether to collect, for each section, the GFM footnote definitions it references which are
+    /// defined outside of it. See [`Section::footnotes`](crate::Section::footnotes).
+    pub footnotes: bool,
+    /// Whether to look for a YAML (`---`) or TOML (`+++`) front matter at the start of the text,
+    /// and leave it out of the first section. This enables the `frontmatter` construct of the
+    /// parser. See [`split_document`](crate::split_document) to get the front matter itself.
+    pub front_matter: bool,
+    /// Whether an HTML comment right before a heading belongs to the heading's section rather than
+    /// to the previous one. If the comment contains a heading, as translations keeping the
+    /// original heading do, it becomes the [`Section::id`](crate::Section::id) of the section.
+    pub comments: bool,
+    /// Whether to also split on headings nested in blockquotes, lists and MDX elements, e.g. a
+    /// `> ### Note` heading. The section then starts at the enclosing top-level block, so that it
+    /// remains valid markdown, and only the first heading within that block splits.
+    pub nested: bool,
+    /// Whether an empty markdown text is split into no sections at all rather than being an
+    /// [`Error::EmptyInput`](crate::Error::EmptyInput).
+    pub allow_empty: bool,
+    /// Top-level nodes other than headings which start a new section, e.g. the `---` between
+    /// slides. A heading right after a marker does not start another section, but becomes the
+    /// heading of the marker's section.
+    pub markers: Vec<Marker>,
+    /// How the headings of the text are found. See [`Backend`].
+    pub backend: Backend,
+}
+
+/// How the headings of a markdown text are found, i.e. with which markdown parser.
+///
+/// Only [`Backend::Mdast`] builds the full AST which [`SplitOptions::markers`],
+/// [`SplitOptions::comments`], [`SplitOptions::nested`], [`SplitOptions::definitions`] and
+/// [`SplitOptions::footnotes`] need. The other backends return an
+/// [`Error::Unsupported`](crate::Error::Unsupported) if one of them is set. Besides,
+/// [`align`](crate::align), [`chunk`](crate::chunk) and the streaming splitters always use the
+/// AST.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Parse the text into a full AST with the `markdown` crate, which supports every option.
+    #[default]
+    Mdast,
+    /// Scan the text line by line for the headings at the top level, which is much faster on large
+    /// texts.
+    ///
+    /// The scanner follows the block structure of CommonMark and GFM as the `markdown` crate does,
+    /// regardless of the `ParseOptions`, which only apply to the plain text of the headings. Each
+    /// heading is parsed on its own, so a heading with a link reference keeps its brackets. The
+    /// headings nested in containers are not found at all, so they do not count to make slugs
+    /// unique, which may then differ from the anchors GitHub generates.
+    ///
+    /// The scanner does not reproduce a quirk of the `markdown` crate, which may miscount the
+    /// cells of a table header row starting with a pipe when a line ending with a pipe is
+    /// followed by a block quote, a list item or a lazy line. Where the `markdown` crate then
+    /// sees no table, e.g. a setext heading in `--|\n* # \n|-\n--|\n=`, the scanner sees a
+    /// table and no heading.
+    Scanner,
+    /// Parse the text with the `pulldown-cmark` crate. Requires the `pulldown-cmark` feature.
+    #[cfg(feature = "pulldown-cmark")]
+    PulldownCmark,
+    /// Parse the text with the `comrak` crate. Requires the `comrak` feature.
+    #[cfg(feature = "comrak")]
+    Comrak,
+}
+
+/// A predicate over top-level nodes of the AST, deciding whether a node starts a new section.
+#[derive(Clone)]
+pub enum Marker {
+    /// A thematic break, i.e. `---`, `***` or `___`.
+    ThematicBreak,
+    /// An HTML comment whose content, without the surrounding whitespace, matches a regular
+    /// expression, e.g. `<!-- split -->`.
+    HtmlComment(Regex),
+    /// Any node the closure returns `true` for.
+    Custom(Arc<dyn Fn(&Node) -> bool + Send + Sync>),
+}
+
+impl Marker {
+    /// An HTML comment marker matching `pattern`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pattern` is not a valid regular expression.
+    pub fn html_comment(pattern: &str) -> Result<Self> {
+        Ok(Self::HtmlComment(Regex::new(pattern)?))
+    }
+
+    /// A marker for any node `predicate` returns `true` for.
+    pub fn custom(predicate: impl Fn(&Node) -> bool + Send + Sync + 'static) -> Self {
+        Self::Custom(Arc::new(predicate))
+    }
+
+    /// Whether `node` starts a new section.
+    pub(crate) fn matches(&self, node: &Node) -> bool {
+        match (self, node) {
+            (Marker::ThematicBreak, ThematicBreak(_)) => true,
+            (Marker::HtmlComment(pattern), _) => {
+                html_comment(node).is_some_and(|comment| pattern.is_match(comment.trim()))
+            }
+            (Marker::Custom(predicate), _) => predicate(node),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Marker::ThematicBreak => f.write_str("ThematicBreak"),
+            Marker::HtmlComment(pattern) => f.debug_tuple("HtmlComment").field(pattern).finish(),
+            Marker::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl Default for SplitOptions {
+    /// Split on every heading, from h1 to h6, and nothing else.
+    fn default() -> Self {
+        Self {
+            headings: true,
+            min_depth: 1,
+            max_depth: 6,
+            definitions: false,
+            footnotes: false,
+            front_matter: false,
+            comments: false,
+            nested: false,
+            allow_empty: false,
+            markers: vec![],
+            backend: Backend::Mdast,
+        }
+    }
+}
+
+impl SplitOptions {
+    /// Whether a heading of the given depth starts a new section.
+    pub(crate) fn splits_on(&self, depth: u8) -> bool {
+        self.headings && (self.min_depth..=self.max_depth).contains(&depth)
+    }
+}
@@ -0,0 +1,75 @@
+use markdown::ParseOptions;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::source::Heading;
+
+/// The headings of a markdown text, as parsed by `pulldown-cmark` with its GFM extensions.
+pub(crate) fn headings(text: &str, options: &ParseOptions) -> Vec<Heading> {
+    let mut extensions = Options::ENABLE_GFM
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    if options.constructs.frontmatter {
+        extensions |= Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+            | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS;
+    }
+
+    let mut headings = vec![];
+    // How many blocks or inlines the parser is in.
+    let mut nesting = 0;
+    // The heading being read, and how many images it is in, whose alt text is not part of it.
+    let mut current: Option<(Heading, usize)> = None;
+    for (event, range) in Parser::new_ext(text, extensions).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let heading = Heading {
+                    range: line_start(text, range.start)..range.end,
+                    depth: level as u8,
+                    text: String::new(),
+                    nested: nesting > 0,
+                };
+                current = Some((heading, 0));
+                nesting += 1;
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                headings.extend(current.take().map(|(heading, _)| heading));
+                nesting -= 1;
+            }
+            Event::Start(tag) => {
+                if let (Some((_, images)), Tag::Image { .. }) = (&mut current, tag) {
+                    *images += 1;
+                }
+                nesting += 1;
+            }
+            Event::End(tag) => {
+                if let (Some((_, images)), TagEnd::Image) = (&mut current, tag) {
+                    *images -= 1;
+                }
+                nesting -= 1;
+            }
+            Event::Text(value)
+            | Event::Code(value)
+            | Event::InlineHtml(value)
+            | Event::Html(value)
+            | Event::InlineMath(value)
+            | Event::DisplayMath(value) => {
+                if let Some((heading, 0)) = &mut current {
+                    heading.text.push_str(&value);
+                }
+            }
+            Event::SoftBreak => {
+                if let Some((heading, 0)) = &mut current {
+                    heading.text.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+/// The offset of the start of the line `offset` is in.
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind(['\n', '\r']).map_or(0, |i| i + 1)
+}
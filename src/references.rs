@@ -0,0 +1,122 @@
+use std::{collections::HashMap, ops::Range};
+
+use markdown::{
+    mdast::{
+        Node,
+        Node::{Definition, FootnoteDefinition, FootnoteReference, ImageReference, LinkReference},
+    },
+    unist::Position,
+};
+
+use crate::section::Section;
+
+/// The kinds of definitions which may live far from where they are referenced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Kind {
+    /// Link reference definitions, e.g. `[install]: https://www.rust-lang.org/tools/install`.
+    Link,
+    /// GFM footnote definitions, e.g. `[^1]: A footnote.`.
+    Footnote,
+}
+
+/// The definitions of the given kinds in a text, along with where they are referenced, to attach
+/// them to the sections referencing them but which do not contain them.
+#[derive(Debug, Clone)]
+pub(crate) struct References<'a> {
+    text: &'a str,
+    kinds: Vec<Kind>,
+    definitions: HashMap<(Kind, String), Range<usize>>,
+    /// The references in the text, in order.
+    references: Vec<(Kind, String, usize)>,
+}
+
+impl<'a> References<'a> {
+    /// Collect the definitions and references of the given kinds in the AST of a text.
+    pub(crate) fn new(text: &'a str, ast: &Node, kinds: Vec<Kind>) -> Self {
+        let mut references = Self { kinds, ..Self::none(text) };
+        if !references.kinds.is_empty() {
+            references.traverse(ast);
+        }
+        references
+    }
+
+    /// No definitions to attach, e.g. when the text is not parsed into an AST.
+    pub(crate) fn none(text: &'a str) -> Self {
+        Self {
+            text,
+            kinds: vec![],
+            definitions: HashMap::new(),
+            references: vec![],
+        }
+    }
+
+    fn traverse(&mut self, node: &Node) {
+        let mut define = |kind, identifier: &str, p: &Position| {
+            // The first definition of an identifier wins, as per CommonMark.
+            self.definitions
+                .entry((kind, identifier.to_string()))
+                .or_insert(p.start.offset..p.end.offset);
+        };
+        let mut reference = |kind, identifier: &str, p: &Position| {
+            self.references.push((kind, identifier.to_string(), p.start.offset))
+        };
+        match (node, node.position()) {
+            (Definition(d), Some(p)) => define(Kind::Link, &d.identifier, p),
+            (FootnoteDefinition(d), Some(p)) => define(Kind::Footnote, &d.identifier, p),
+            (LinkReference(r), Some(p)) => reference(Kind::Link, &r.identifier, p),
+            (ImageReference(r), Some(p)) => reference(Kind::Link, &r.identifier, p),
+            (FootnoteReference(r), Some(p)) => reference(Kind::Footnote, &r.identifier, p),
+            _ => {}
+        }
+        if let Some(children) = node.children() {
+            children.iter().for_each(|c| self.traverse(c));
+        }
+    }
+
+    /// Attach to a section the definitions it references but which live outside of it, in the
+    /// order they are first referenced.
+    pub(crate) fn attach(&self, section: &mut Section<'a>) {
+        let range = section.range.clone();
+        let first = self
+            .references
+            .partition_point(|(_, _, offset)| *offset < range.start);
+        for (kind, identifier, _) in self.references[first..]
+            .iter()
+            .take_while(|(_, _, offset)| *offset < range.end)
+        {
+            if !self.kinds.contains(kind) {
+                continue;
+            }
+            let Some(definition) = self.definitions.get(&(*kind, identifier.clone())) else {
+                continue;
+            };
+            let slice = self.text[definition.clone()].trim_end();
+            let attached = match kind {
+                Kind::Link => &mut section.definitions,
+                Kind::Footnote => &mut section.footnotes,
+            };
+            if !range.contains(&definition.start) && !attached.contains(&slice) {
+                attached.push(slice);
+            }
+        }
+    }
+}
+
+/// Append definitions to the text of a section, separated from it by a blank line.
+pub(crate) fn append(text: &str, definitions: &[&str]) -> String {
+    let mut output = text.to_string();
+    if definitions.is_empty() {
+        return output;
+    }
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+    if !output.ends_with("\n\n") {
+        output.push('\n');
+    }
+    definitions.iter().for_each(|d| {
+        output.push_str(d);
+        output.push('\n');
+    });
+    output
+}
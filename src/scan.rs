@@ -0,0 +1,881 @@
+use std::{
+    borrow::Cow,
+    ops::Range,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::LazyLock,
+};
+
+use markdown::{
+    mdast::Node::{Heading as HeadingNode, Root},
+    to_mdast, ParseOptions,
+};
+use regex::Regex;
+
+use crate::{front_matter::scan_front_matter, source::Heading};
+
+/// Find the headings at the top level of a markdown text line by line, without building its AST
+///
+/// Only the block structure is followed: fenced and indented code blocks, HTML blocks, link
+/// reference definitions, tables, and the block quotes, list items and footnote definitions whose
+/// headings are not at the top level. The inline content is left alone, but for the text of the
+/// headings. The front matter is skipped if the `frontmatter` construct of `options` is enabled.
+pub(crate) fn headings(text: &str, options: &ParseOptions) -> Vec<Heading> {
+    scan(text, options.constructs.frontmatter)
+        .into_iter()
+        .map(|(range, depth)| Heading {
+            text: heading_text(&text[range.clone()], options),
+            range,
+            depth,
+            nested: false,
+        })
+        .collect()
+}
+
+/// The plain text of a heading, parsed on its own. Falls back to its source if the parser fails.
+fn heading_text(source: &str, options: &ParseOptions) -> String {
+    let ast = catch_unwind(AssertUnwindSafe(|| to_mdast(source, options)));
+    match ast {
+        Ok(Ok(Root(root))) => match root.children.first() {
+            Some(heading @ HeadingNode(_)) => heading.to_string(),
+            _ => source.trim().to_string(),
+        },
+        _ => source.trim().to_string(),
+    }
+}
+
+/// The range and the depth of each heading at the top level of a markdown text, in order.
+fn scan(text: &str, front_matter: bool) -> Vec<(Range<usize>, u8)> {
+    let mut headings = vec![];
+    let mut lines = Lines::new(text).peekable();
+
+    let fenced = front_matter.then(|| scan_front_matter(text)).flatten();
+    if let Some(fenced) = &fenced {
+        while lines.next_if(|(offset, _)| *offset < fenced.range.end).is_some() {}
+    }
+
+    // The `markdown` crate does not recognize containers at all after a front matter fence which is
+    // never closed.
+    let fence = Lines::new(text)
+        .next()
+        .map(|(_, line)| line.trim_end_matches([' ', '\t']));
+    let flat = front_matter && fenced.is_none() && matches!(fence, Some("---" | "+++"));
+    let mut blocks = Blocks { flat, ..Default::default() };
+    for (offset, line) in lines {
+        let crlf = text[offset + line.len()..].starts_with("\r\n");
+        let line = expand_tabs(line);
+        if let Some(heading) = blocks.line(offset, &line, crlf) {
+            headings.push(heading);
+        }
+    }
+    headings
+}
+
+/// The lines of a text along with their offsets, without their line endings, which are `\n`,
+/// `\r\n` or `\r` as in CommonMark.
+pub(crate) struct Lines<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lines<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Self { text, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.text[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        let start = self.offset;
+        let end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        self.offset += end
+            + match &rest[end..] {
+                r if r.starts_with("\r\n") => 2,
+                "" => 0,
+                _ => 1,
+            };
+        Some((start, &rest[..end]))
+    }
+}
+
+/// Replace the tabs of a line with spaces up to the next multiple of 4 columns, which is how
+/// CommonMark counts indentation.
+fn expand_tabs(line: &str) -> Cow<'_, str> {
+    if !line.contains('\t') {
+        return Cow::Borrowed(line);
+    }
+    let mut expanded = String::with_capacity(line.len() + 8);
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let width = 4 - column % 4;
+            expanded.extend(std::iter::repeat_n(' ', width));
+            column += width;
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+    Cow::Owned(expanded)
+}
+
+/// The blocks open at one level of nesting, i.e. at the top level or in a container block.
+#[derive(Debug, Default)]
+struct Blocks {
+    open: Open,
+    /// Whether block quotes, list items and footnote definitions are left unrecognized.
+    flat: bool,
+}
+
+/// The block which is open and may continue on the next line.
+#[derive(Debug, Default)]
+enum Open {
+    #[default]
+    None,
+    Paragraph {
+        /// Where the first line which is not part of a link reference definition starts.
+        content: Option<usize>,
+        /// The lines of a link reference definition which may not be complete yet.
+        definition: String,
+        /// Where the link reference definition being read starts.
+        start: usize,
+        /// Whether the last line completed a link reference definition without a title, which
+        /// may still follow on the next line.
+        title: bool,
+        /// The number of cells of the last line, which may be the header row of a table.
+        cells: Option<usize>,
+        /// Whether the paragraph started on the line closing a container, after which the
+        /// `markdown` crate lets any list item, even an empty one, interrupt it.
+        interruptible: bool,
+        /// Whether indented code and any HTML block may interrupt it too, as they do if the
+        /// paragraph also starts with one of [`SHORTCUTS`].
+        code: bool,
+        /// Whether a line was added, unlike to the phantom paragraph the first line of a container
+        /// interrupting a paragraph is read in.
+        started: bool,
+    },
+    /// An underline right after link reference definitions or a setext heading, which only another
+    /// underline continues, into a heading.
+    Underline(usize),
+    /// A setext heading, after which an underline is a paragraph rather than a thematic break.
+    Setext,
+    Table,
+    Fenced {
+        fence: char,
+        length: usize,
+    },
+    Indented,
+    Html(&'static str),
+    Quote(Box<Blocks>),
+    Item {
+        /// The indentation of the content of the item, or of a footnote definition.
+        indent: usize,
+        /// Whether the item has been blank so far.
+        empty: bool,
+        content: Box<Blocks>,
+    },
+}
+
+impl Open {
+    /// A paragraph which has not started yet.
+    fn paragraph() -> Self {
+        Open::Paragraph {
+            content: None,
+            definition: String::new(),
+            start: 0,
+            title: false,
+            cells: None,
+            interruptible: false,
+            code: false,
+            started: false,
+        }
+    }
+}
+
+/// The characters after which the `markdown` crate only tries the constructs starting with them
+/// before falling back to a paragraph, so that a line starting with one is never a row of a table.
+const SHORTCUTS: [char; 9] = ['#', '$', '`', '~', '*', '_', 'e', 'i', '{'];
+
+/// The end of an HTML block which ends at a blank line rather than at a given string.
+const BLANK: &str = "";
+
+impl Blocks {
+    /// Take the next line, and return the range and the depth of the heading it ends, if any. Only
+    /// meaningful at the top level, where `offset` is the offset of the line in the text. `crlf`
+    /// tells whether the line ends with `\r\n`.
+    fn line(&mut self, offset: usize, line: &str, crlf: bool) -> Option<(Range<usize>, u8)> {
+        let blank = is_blank(line);
+        // Whether the line closes a container, unless it continues it.
+        let container = matches!(self.open, Open::Quote(_) | Open::Item { .. });
+        match &mut self.open {
+            Open::Fenced { fence, length } => {
+                if closes_fence(line, *fence, *length) {
+                    self.open = Open::None;
+                }
+                return None;
+            }
+            Open::Indented if blank || indent(line) >= 4 => return None,
+            Open::Html(end) => {
+                let ends = match *end {
+                    BLANK => blank,
+                    end if end.starts_with("</") => contains_ignore_case(line, end),
+                    end => line.contains(end),
+                };
+                if ends {
+                    self.open = Open::None;
+                }
+                return None;
+            }
+            Open::Quote(content) => {
+                if let Some(rest) = strip_quote(line) {
+                    content.line(0, rest, crlf);
+                    return None;
+                }
+                if !blank && content.is_lazy() && !interrupts_paragraph(line, true) {
+                    return None;
+                }
+            }
+            Open::Item { indent: width, empty, content } => {
+                if blank {
+                    // An item may only start with one blank line.
+                    if !*empty {
+                        content.line(0, "", crlf);
+                        return None;
+                    }
+                } else if indent(line) >= *width {
+                    *empty = false;
+                    content.line(0, &line[*width..], crlf);
+                    return None;
+                } else if content.is_lazy() && !interrupts_paragraph(line, true) {
+                    return None;
+                }
+            }
+            Open::Underline(start) => {
+                let start = *start;
+                self.open = Open::None;
+                if let Some(depth) = lone_underline(line, !self.flat) {
+                    self.open = Open::Setext;
+                    return Some((start..offset + line.len(), depth));
+                }
+            }
+            Open::Setext => {
+                self.open = Open::None;
+                if lone_underline(line, !self.flat).is_some() {
+                    self.open = Open::Underline(offset);
+                    return None;
+                }
+            }
+            Open::Table
+                if !blank && !starts_block(line, !self.flat) && !line.starts_with(SHORTCUTS) =>
+            {
+                return None
+            }
+            _ => {}
+        }
+
+        let paragraph = matches!(self.open, Open::Paragraph { .. });
+        let (interruptible, code) = match self.open {
+            Open::Paragraph { interruptible, code, .. } => (interruptible, code),
+            _ => (false, false),
+        };
+        let any_item = interruptible && list_item(line, false).is_some();
+        let phantom = matches!(self.open, Open::Paragraph { started: false, .. });
+        if !paragraph {
+            self.open = Open::None;
+        }
+        if blank {
+            self.open = Open::None;
+            return None;
+        }
+
+        let indent = indent(line);
+        if paragraph && indent < 4 && !any_item && !phantom {
+            if let Some(depth) = setext_underline(line) {
+                let Open::Paragraph { content, definition, start, .. } = &self.open else {
+                    unreachable!()
+                };
+                let start = match content {
+                    Some(content) => Some(*content),
+                    // A definition which is not complete yet is the content of the heading.
+                    None => (!definition.is_empty()).then_some(*start),
+                };
+                if let Some(start) = start {
+                    self.open = Open::Setext;
+                    return Some((start..offset + line.len(), depth));
+                }
+                // An underline right after definitions is a paragraph of its own, even `---`.
+                self.open = Open::Underline(offset);
+                return None;
+            }
+            if let Open::Paragraph { cells: Some(cells), .. } = self.open {
+                // A list item takes precedence over a delimiter row, e.g. `- |`.
+                let item = !self.flat && list_item(line, true).is_some();
+                if delimiter_row(line) == Some(cells) && !item {
+                    self.open = Open::Table;
+                    return None;
+                }
+            }
+        }
+        if indent >= 4 {
+            if paragraph && !code {
+                self.continue_paragraph(offset, line);
+            } else {
+                self.open = Open::Indented;
+            }
+            return None;
+        }
+
+        let rest = &line[indent..];
+        if let Some(depth) = atx_heading(rest) {
+            self.open = Open::None;
+            return Some((offset..offset + line.len(), depth));
+        }
+        if let Some((fence, length)) = opens_fence(rest) {
+            self.open = Open::Fenced { fence, length };
+            return None;
+        }
+        if let Some((end, start)) = html_block(rest, paragraph && !code) {
+            let ends = match end {
+                BLANK => false,
+                end if end.starts_with("</") => contains_ignore_case(rest, end),
+                end => rest[start..].contains(end),
+            };
+            self.open = if ends { Open::None } else { Open::Html(end) };
+            return None;
+        }
+        if is_thematic_break(rest) {
+            self.open = Open::None;
+            return None;
+        }
+        if let Some(rest) = strip_quote(line).filter(|_| !self.flat) {
+            let mut content = Blocks::default();
+            if paragraph {
+                // The `markdown` crate reads the first line of a container interrupting a
+                // paragraph as if it could only interrupt that paragraph too, so that e.g. `-` is
+                // not an empty list item then.
+                content.open = Open::paragraph();
+            }
+            content.line(0, rest, crlf);
+            self.open = Open::Quote(Box::new(content));
+            return None;
+        }
+        let list = list_item(line, paragraph && !any_item);
+        if let Some((width, rest)) = list.or_else(|| footnote(line)).filter(|_| !self.flat) {
+            let mut content = Blocks::default();
+            // Unlike a list item, a footnote definition may start with several blank lines.
+            let empty = list.is_some() && is_blank(rest);
+            // The `markdown` crate ends an item right away if its marker is followed by `\r\n`.
+            if empty && crlf && !line.ends_with(' ') {
+                self.open = Open::None;
+                return None;
+            }
+            if !empty {
+                if paragraph {
+                    // Likewise for the first line of an item, see the block quote above.
+                    content.open = Open::paragraph();
+                }
+                content.line(0, rest, crlf);
+            }
+            self.open = Open::Item { indent: width, empty, content: Box::new(content) };
+            return None;
+        }
+
+        if paragraph {
+            self.continue_paragraph(offset, line);
+        } else {
+            self.open = Open::paragraph();
+            self.continue_paragraph(offset, line);
+            if let Open::Paragraph { interruptible, code, .. } = &mut self.open {
+                if container {
+                    // A lone pipe is only known not to be a table row at its end, like a line
+                    // starting with a shortcut is only known not to be its construct.
+                    *interruptible = true;
+                    *code = line.starts_with(SHORTCUTS) || line.trim() == "|";
+                }
+            }
+        }
+        None
+    }
+
+    /// Add a line of text to the open paragraph, which may be part of a link reference definition
+    /// as long as no other text came before.
+    fn continue_paragraph(&mut self, offset: usize, line: &str) {
+        let Open::Paragraph {
+            content,
+            definition,
+            start,
+            title,
+            cells,
+            interruptible,
+            code,
+            started,
+        } = &mut self.open
+        else {
+            return;
+        };
+        (*interruptible, *code, *started) = (false, false, true);
+        let header = !line.starts_with(SHORTCUTS) && indent(line) < 4;
+        *cells = header.then(|| self::cells(line).len());
+        if content.is_some() {
+            return;
+        }
+        if *title && TITLE.is_match(line) {
+            *title = false;
+            return;
+        }
+        if definition.is_empty() {
+            *start = offset;
+        } else {
+            definition.push('\n');
+        }
+        definition.push_str(line.trim_start_matches(' '));
+        let captures = DEFINITION
+            .captures(definition)
+            .filter(|captures| captures.name("raw").is_none_or(|raw| balanced(raw.as_str())));
+        if let Some(captures) = captures {
+            *title = captures.name("title").is_none();
+            definition.clear();
+        } else if !PARTIAL_DEFINITION.is_match(definition) {
+            // The lines read so far as a definition turned out not to be one.
+            *content = Some(*start);
+            *title = false;
+        } else {
+            *title = false;
+        }
+    }
+
+    /// Whether the innermost open block is a paragraph, which a line may lazily continue.
+    fn is_lazy(&self) -> bool {
+        match &self.open {
+            Open::Paragraph { .. } => true,
+            Open::Quote(content) | Open::Item { content, .. } => content.is_lazy(),
+            _ => false,
+        }
+    }
+}
+
+/// A complete link reference definition, with its optional title in the first group.
+static DEFINITION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^ {0,3}\[[^\[\]]*[^\[\]\s][^\[\]]*\]:[ ]*\n?[ ]*(?:<[^<>\n]*>|(?P<raw>[^\s<][^\s]*))(?:[ ]*\n?[ ]*(?P<title>"[^"]*"|'[^']*'|\([^()]*\)))?[ ]*$"#,
+    )
+    .unwrap()
+});
+
+/// Whether the parentheses of a link destination which is not in angle brackets are balanced.
+fn balanced(destination: &str) -> bool {
+    let mut depth = 0usize;
+    let mut escaped = false;
+    for c in destination.chars() {
+        match c {
+            '(' if !escaped => depth += 1,
+            ')' if !escaped => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => {}
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    depth == 0
+}
+
+/// The start of a link reference definition, which may be completed by the next lines.
+static PARTIAL_DEFINITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}\[[^\[\]]*[^\[\]\s][^\[\]]*\]:[ ]*$").unwrap());
+
+/// The title of a link reference definition on its own line.
+static TITLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^[ ]*(?:"[^"]*"|'[^']*'|\([^()]*\))[ ]*$"#).unwrap());
+
+fn is_blank(line: &str) -> bool {
+    line.bytes().all(|b| b == b' ')
+}
+
+/// The number of columns a line is indented by, tabs being expanded already.
+fn indent(line: &str) -> usize {
+    line.bytes().take_while(|&b| b == b' ').count()
+}
+
+/// The depth of an ATX heading, e.g. `## Installation`.
+fn atx_heading(rest: &str) -> Option<u8> {
+    let depth = rest.bytes().take_while(|&b| b == b'#').count();
+    let after = &rest[depth..];
+    ((1..=6).contains(&depth) && (after.is_empty() || after.starts_with(' ')))
+        .then_some(depth as u8)
+}
+
+/// The depth of the heading a setext underline makes of the paragraph before it.
+fn setext_underline(line: &str) -> Option<u8> {
+    let rest = line.trim_start_matches(' ').trim_end_matches(' ');
+    let c = rest.chars().next()?;
+    let depth = match c {
+        '=' => 1,
+        '-' => 2,
+        _ => return None,
+    };
+    rest.chars().all(|ch| ch == c).then_some(depth)
+}
+
+/// The depth of a setext underline which is not under a paragraph, and is not an empty list item
+/// either when containers are recognized.
+fn lone_underline(line: &str, containers: bool) -> Option<u8> {
+    setext_underline(line)
+        .filter(|_| indent(line) < 4 && !(containers && list_item(line, false).is_some()))
+}
+
+fn is_thematic_break(rest: &str) -> bool {
+    let Some(c) = rest.chars().next().filter(|c| matches!(c, '*' | '-' | '_')) else {
+        return false;
+    };
+    rest.chars().all(|ch| ch == c || ch == ' ') && rest.chars().filter(|&ch| ch == c).count() >= 3
+}
+
+/// The character and the length of the fence opening a fenced code block.
+fn opens_fence(rest: &str) -> Option<(char, usize)> {
+    let fence = rest.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let length = rest.chars().take_while(|&c| c == fence).count();
+    let info = &rest[length..];
+    (length >= 3 && !(fence == '`' && info.contains('`'))).then_some((fence, length))
+}
+
+fn closes_fence(line: &str, fence: char, length: usize) -> bool {
+    let rest = line.trim_start_matches(' ');
+    let count = rest.chars().take_while(|&c| c == fence).count();
+    line.len() - rest.len() < 4 && count >= length && is_blank(&rest[count..])
+}
+
+/// Strip the `>` of a block quote line, along with the optional space following it.
+fn strip_quote(line: &str) -> Option<&str> {
+    let indent = indent(line);
+    let rest = line[indent..].strip_prefix('>').filter(|_| indent < 4)?;
+    Some(rest.strip_prefix(' ').unwrap_or(rest))
+}
+
+/// The indentation of the content of a list item and the content of its first line.
+fn list_item(line: &str, paragraph: bool) -> Option<(usize, &str)> {
+    let start = indent(line);
+    let rest = &line[start..];
+    let (marker, ordered) = match rest.bytes().next()? {
+        b'-' | b'+' | b'*' => (1, None),
+        _ => {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let delimiter = rest[digits..].bytes().next();
+            if !(1..=9).contains(&digits) || !matches!(delimiter, Some(b'.' | b')')) {
+                return None;
+            }
+            (digits + 1, Some(&rest[..digits]))
+        }
+    };
+    if start >= 4 {
+        return None;
+    }
+    let after = &rest[marker..];
+    let spaces = indent(after);
+    if is_blank(after) {
+        // An empty item cannot interrupt a paragraph.
+        return (!paragraph).then_some((start + marker + 1, ""));
+    }
+    if spaces == 0 || paragraph && ordered.is_some_and(|n| n.parse() != Ok(1)) {
+        return None;
+    }
+    let spaces = if spaces > 4 { 1 } else { spaces };
+    Some((start + marker + spaces, &after[spaces..]))
+}
+
+/// The content of the first line of a footnote definition, e.g. `[^1]: A footnote.`, whose content
+/// is indented by 4 columns.
+fn footnote(line: &str) -> Option<(usize, &str)> {
+    let indent = indent(line);
+    let rest = line[indent..].strip_prefix("[^").filter(|_| indent < 4)?;
+    let (label, after) = rest.split_once("]:")?;
+    if label.is_empty() || label.contains(['[', ']', ' ']) {
+        return None;
+    }
+    Some((4, after.trim_start_matches(' ')))
+}
+
+/// The end of the HTML block a line starts, if any, along with where to look for it in the line.
+fn html_block(rest: &str, paragraph: bool) -> Option<(&'static str, usize)> {
+    static RAW: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?i)^<(script|pre|style|textarea)(?:[ >]|$)").unwrap());
+    static BLOCK: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r"(?i)^</?(?:address|article|aside|base|basefont|blockquote|body|caption|center|col|colgroup|dd|details|dialog|dir|div|dl|dt|fieldset|figcaption|figure|footer|form|frame|frameset|h1|h2|h3|h4|h5|h6|head|header|hr|html|iframe|legend|li|link|main|menu|menuitem|nav|noframes|ol|optgroup|option|p|param|search|section|summary|table|tbody|td|tfoot|th|thead|title|tr|track|ul)(?:[ >]|/>|$)",
+        )
+        .unwrap()
+    });
+    static TAG: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r#"^(?:<[A-Za-z][A-Za-z0-9-]*(?:[ ]+[A-Za-z_:][A-Za-z0-9_.:-]*(?:[ ]*=[ ]*(?:[^ "'=<>`]+|'[^']*'|"[^"]*"))?)*[ ]*/?>|</[A-Za-z][A-Za-z0-9-]*[ ]*>)[ ]*$"#,
+        )
+        .unwrap()
+    });
+
+    if !rest.starts_with('<') {
+        return None;
+    }
+    if let Some(captures) = RAW.captures(rest) {
+        let end = match captures[1].to_ascii_lowercase().as_str() {
+            "script" => "</script>",
+            "pre" => "</pre>",
+            "style" => "</style>",
+            _ => "</textarea>",
+        };
+        return Some((end, 0));
+    }
+    if rest.starts_with("<!--") {
+        return Some(("-->", 2));
+    }
+    if rest.starts_with("<?") {
+        return Some(("?>", 2));
+    }
+    if rest.starts_with("<![CDATA[") {
+        return Some(("]]>", 9));
+    }
+    if rest.starts_with("<!") && rest[2..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Some((">", 2));
+    }
+    if BLOCK.is_match(rest) {
+        return Some((BLANK, 0));
+    }
+    (!paragraph && TAG.is_match(rest)).then_some((BLANK, 0))
+}
+
+fn contains_ignore_case(line: &str, end: &str) -> bool {
+    line.to_ascii_lowercase().contains(end)
+}
+
+/// Whether a line starts a block rather than lazily continuing a paragraph in a container,
+/// including containers unless told otherwise. Unlike within a paragraph, any list item and any
+/// HTML block does with the `markdown` crate.
+fn interrupts_paragraph(line: &str, containers: bool) -> bool {
+    let indent = indent(line);
+    if indent >= 4 {
+        return false;
+    }
+    let rest = &line[indent..];
+    atx_heading(rest).is_some()
+        || opens_fence(rest).is_some()
+        || html_block(rest, false).is_some()
+        || is_thematic_break(rest)
+        || containers
+            && (strip_quote(line).is_some()
+                || list_item(line, false).is_some()
+                || footnote(line).is_some())
+}
+
+/// Whether a line starts a block which ends a table.
+fn starts_block(line: &str, containers: bool) -> bool {
+    indent(line) >= 4 || interrupts_paragraph(line, containers)
+}
+
+/// The number of cells of a table delimiter row, e.g. `| --- | :-: |`.
+fn delimiter_row(line: &str) -> Option<usize> {
+    if !line.contains('|') && !line.contains(':') {
+        return None;
+    }
+    let cells = cells(line);
+    (!cells.is_empty()
+        && cells.iter().all(|cell| {
+            let cell = cell.trim_matches(' ');
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.bytes().all(|b| b == b'-')
+        }))
+    .then_some(cells.len())
+}
+
+/// The cells of a table row, split at the pipes which are not escaped.
+fn cells(line: &str) -> Vec<&str> {
+    let line = line.trim_matches(' ');
+    let line = line.strip_prefix('|').unwrap_or(line);
+    if is_blank(line) {
+        return vec![];
+    }
+    let line = match line.strip_suffix('|') {
+        Some(stripped) if !stripped.ends_with('\\') => stripped,
+        _ => line,
+    };
+    let mut cells = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '|' if !escaped => {
+                cells.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => escaped = false,
+        }
+    }
+    cells.push(&line[start..]);
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        error::Result,
+        front_matter::FrontMatterKind,
+        join::tests::{both, markdown},
+        options::{Backend, SplitOptions},
+        section::Section,
+        split::split_sections_with,
+    };
+
+    /// Split a text with the scanner.
+    fn scanned<'a>(text: &'a str, options: &SplitOptions) -> Result<Vec<Section<'a>>> {
+        let scanner = SplitOptions { backend: Backend::Scanner, ..options.clone() };
+        split_sections_with(text, None, &scanner)
+    }
+
+    /// The range and the heading depth of each section.
+    fn boundaries(sections: &[Section]) -> Vec<(Range<usize>, Option<u8>)> {
+        sections.iter().map(|s| (s.range.clone(), s.depth)).collect()
+    }
+
+    #[test]
+    fn test_fixtures() {
+        for language in ["en", "ja"] {
+            let text = read_to_string(format!("tests/fixtures/ch01-01-installation.{language}.md"))
+                .unwrap();
+            for options in [
+                SplitOptions::default(),
+                SplitOptions { min_depth: 3, ..Default::default() },
+                SplitOptions {
+                    max_depth: 2,
+                    front_matter: true,
+                    ..Default::default()
+                },
+            ] {
+                let (expected, actual) = both(&text, &options, scanned).unwrap();
+                assert_eq!(actual.unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blocks() {
+        for text in [
+            "```\n# a\n```\n# b\n",
+            "~~~~\n# a\n~~~\n# b\n~~~~\n# c\n",
+            "    # a\n\t# b\n# c\n",
+            "<!--\n# a\n-->\n# b\n<!-->\n# c\n",
+            "<div>\n# a\n\n# b\n",
+            "<a>\n# a\n\np\n<a>\n# b\n",
+            "<pre>\n\n# a\n</PRE>\n# b\n",
+            "> # a\n> b\n# c\n",
+            "> a\nb\n---\n",
+            "- a\n\n  # b\n# c\n",
+            "-\n\n  # a\n",
+            "x\n2. a\n# b\n1. c\n---\n",
+            "[^1]: a\n\n    # b\n\n  # c\n",
+            "a|b\n-|-\n# c\nd\n===\n",
+            "a\nb|c\n-|-\nd\n---\n",
+            "|b\n-\n",
+            "[a]: /url\n===\n[b]:\n /url\n 'title'\nc\n---\n",
+            "Title\r===\r\rText\r\n## Sub ##\r\n",
+            "---\ntitle: a\n---\n# b\n",
+            "a\n    b\n---\n",
+            "***\n---\n- - -\n",
+            "x\n>-\n[a]: /u\na\n=\n",
+            "-\r\n  # a\r\n",
+        ] {
+            let options = SplitOptions { front_matter: true, ..Default::default() };
+            for options in [SplitOptions::default(), options] {
+                let (expected, actual) = both(text, &options, scanned).unwrap();
+                assert_eq!(boundaries(&actual.unwrap()), boundaries(&expected), "{text:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_stale_table_state() {
+        // The `markdown` crate miscounts the cells of `|-` after `--|` and a list item, so that it
+        // sees a setext heading rather than a table. The scanner sees a table, see
+        // `Backend::Scanner`.
+        let text = "--|\n* # \n|-\n--|\n=";
+        assert!(stale_table_state(text));
+        let (expected, actual) = both(text, &SplitOptions::default(), scanned).unwrap();
+        assert_eq!(boundaries(&expected), [(0..9, None), (9..17, Some(1))]);
+        assert_eq!(boundaries(&actual.unwrap()), [(0..17, None)]);
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let text = "---\ntitle: Installation\n---\n\n# A\n";
+        let options = SplitOptions {
+            front_matter: true,
+            backend: Backend::Scanner,
+            ..Default::default()
+        };
+        let document = crate::split_document(text, None, &options).unwrap();
+        let front_matter = document.front_matter.unwrap();
+        assert_eq!(front_matter.kind, FrontMatterKind::Yaml);
+        assert_eq!(front_matter.raw, "title: Installation");
+        assert_eq!(document.sections[0].text, "# A\n");
+    }
+
+    /// Lines of markdown which open, continue or close the blocks the scanner keeps track of.
+    fn blocks() -> impl Strategy<Value = String> {
+        let line = prop_oneof![
+            "(#{1,3}|=+|-+|\\*\\*\\*) ?[a-z]{0,3}",
+            "[a-z|]{0,6}",
+            "(\\| ?)?(:?-+:? ?\\|? ?){1,3}",
+            "(```|~~~|````)[a-z`]{0,2}",
+            "(<!--|-->|<div>|</div>|<pre>|</pre>|<a>|<\\?|\\?>|<!A|>|<!\\[CDATA\\[|]]>)",
+            "( {0,5}|\t)([->*+] ?|1[.)] ?|2\\. |\\[\\^1\\]: ?){0,2}(# )?[a-z]{0,3}",
+            "\\[[a-z]\\]:( /u)?( \"t\")?",
+            Just(String::new()),
+        ];
+        (prop::collection::vec(line, 1..16), any::<bool>())
+            .prop_map(|(lines, newline)| lines.join(if newline { "\n" } else { "\r\n" }))
+    }
+
+    /// Whether the `markdown` crate may miscount the cells of a table header row starting with a
+    /// pipe: a line ending with a pipe which is followed by a container or a lazy line leaves the
+    /// state of its table construct behind, which the scanner does not reproduce.
+    fn stale_table_state(text: &str) -> bool {
+        let container = Regex::new(r"^ {0,3}(>|[-*+]( |$)|[0-9][.)]( |$)|\[\^)").unwrap();
+        let lines = Lines::new(text).map(|(_, line)| line).collect::<Vec<_>>();
+        let Some(first) = lines.iter().position(|line| container.is_match(line)) else {
+            return false;
+        };
+        (first.saturating_sub(1)..lines.len())
+            .find(|&i| lines[i].trim_end().ends_with('|'))
+            .is_some_and(|i| {
+                lines[i + 1..].iter().any(|line| {
+                    line.trim_start_matches(|c: char| {
+                        " \t>-*+.)[]^".contains(c) || c.is_ascii_digit()
+                    })
+                    .starts_with('|')
+                })
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_split_points(text in prop_oneof![markdown(), blocks()]) {
+            if stale_table_state(&text) {
+                return Ok(());
+            }
+            let options = SplitOptions { front_matter: true, ..Default::default() };
+            for options in [SplitOptions::default(), options] {
+                if let Some((expected, actual)) = both(&text, &options, scanned) {
+                    prop_assert_eq!(boundaries(&actual.unwrap()), boundaries(&expected));
+                }
+            }
+        }
+    }
+}
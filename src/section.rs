@@ -0,0 +1,113 @@
+use std::{fmt, ops::Range};
+
+use crate::{front_matter::FrontMatter, location::Location, references::append};
+
+/// A markdown text split into its front matter and its sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Document<'a> {
+    /// The front matter at the start of the text, if any.
+    pub front_matter: Option<FrontMatter<'a>>,
+    /// The sections of the text following the front matter.
+    pub sections: Vec<Section<'a>>,
+}
+
+/// A section of a markdown text, starting at a heading or at the start of the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Section<'a> {
+    /// The slice of the original markdown text covered by this section, including the heading.
+    pub text: &'a str,
+    /// The depth of the heading (`1` for h1 to `6` for h6), or `None` for the text before the
+    /// first heading.
+    pub depth: Option<u8>,
+    /// The plain text of the heading, with inline markup such as emphasis or code and any
+    /// `{#custom-id}` attribute removed, or `None` for the text before the first heading.
+    pub heading: Option<String>,
+    /// A stable identifier of this section, independent of the language of its heading: either the
+    /// explicit `{#custom-id}` attribute of the heading, or the plain text of the original heading
+    /// commented out right before the heading of a translated text, which is only looked for when
+    /// [`SplitOptions::comments`](crate::SplitOptions::comments) is set.
+    pub id: Option<String>,
+    /// The anchor of the heading, i.e. its `{#custom-id}` attribute or the slug GitHub generates
+    /// for it, suffixed with `-1`, `-2` and so on if an earlier heading has the same slug.
+    /// `None` for the text before the first heading. See [`slug`](crate::slug).
+    pub slug: Option<String>,
+    /// The plain text of the headings of the enclosing sections and of this section, from the
+    /// outermost to this one, e.g. `["Installation", "Troubleshooting"]`. Empty for the text
+    /// before the first heading.
+    pub path: Vec<String>,
+    /// The byte range of the heading in the original markdown text, without its indentation nor
+    /// the whitespace and line ending it ends with: from its first `#`, or the first character of
+    /// its text for a setext heading, to the end of its closing `#`s, of its text, or of its
+    /// setext underline. `None` for the text before the first heading.
+    pub heading_range: Option<Range<usize>>,
+    /// The link reference definitions used in this section but defined outside of it, as slices of
+    /// the original markdown text, e.g. `[install]: https://www.rust-lang.org/tools/install`. Only
+    /// collected when [`SplitOptions::definitions`](crate::SplitOptions::definitions) is set.
+    pub definitions: Vec<&'a str>,
+    /// The footnote definitions referenced in this section but defined outside of it, as slices of
+    /// the original markdown text, e.g. `[^1]: A footnote.`. Only collected when
+    /// [`SplitOptions::footnotes`](crate::SplitOptions::footnotes) is set.
+    pub footnotes: Vec<&'a str>,
+    /// The byte range of this section in the original markdown text.
+    pub range: Range<usize>,
+    /// The location where this section starts in the original markdown text.
+    pub start: Location,
+    /// The location where this section ends in the original markdown text, exclusive, i.e. usually
+    /// the start of the line following the section.
+    pub end: Location,
+}
+
+impl<'a> Section<'a> {
+    /// Returns the slice of the original markdown text covered by this section.
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the text of this section with the link reference definitions it needs appended, so
+    /// that its links are not broken when it is rendered on its own.
+    pub fn with_definitions(&self) -> String {
+        append(self.text, &self.definitions)
+    }
+
+    /// Returns the text of this section with the footnote definitions it references appended, so
+    /// that its footnotes are not lost when it is rendered on its own.
+    pub fn with_footnotes(&self) -> String {
+        append(self.text, &self.footnotes)
+    }
+
+    /// Returns the text of this section prefixed with its heading path, joined by `separator`, so
+    /// that the section keeps its context when it is embedded or indexed in isolation.
+    ///
+    /// ```
+    /// use markdown_split::split_sections;
+    ///
+    /// let text = "# Installation\n\n## Troubleshooting\n\nTry again.\n";
+    /// let sections = split_sections(text, None)?;
+    /// assert_eq!(
+    ///     sections[1].with_context(" > "),
+    ///     "Installation > Troubleshooting\n\n## Troubleshooting\n\nTry again.\n"
+    /// );
+    /// # Ok::<(), markdown_split::Error>(())
+    /// ```
+    pub fn with_context(&self, separator: &str) -> String {
+        if self.path.is_empty() {
+            self.text.to_string()
+        } else {
+            format!("{}\n\n{}", self.path.join(separator), self.text)
+        }
+    }
+}
+
+impl AsRef<str> for Section<'_> {
+    fn as_ref(&self) -> &str {
+        self.text
+    }
+}
+
+impl fmt::Display for Section<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text)
+    }
+}
@@ -0,0 +1,82 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Measures the size of a text, e.g. to keep chunks within the budget of an embedding model.
+///
+/// Any `Fn(&str) -> usize` closure is a `Sizer`, which makes it easy to plug in a tokenizer:
+///
+/// ```
+/// use markdown_split::{chunk, ChunkOptions};
+///
+/// let count_tokens = |text: &str| text.split_whitespace().count() * 4 / 3;
+/// let options = ChunkOptions::new(256).with_sizer(count_tokens);
+/// let chunks = chunk("# Hello\n\nWorld\n", None, &options).unwrap();
+/// assert_eq!(chunks, vec!["# Hello\n\nWorld\n"]);
+/// ```
+pub trait Sizer {
+    /// Returns the size of `text`.
+    fn size(&self, text: &str) -> usize;
+}
+
+/// Measures a text in UTF-8 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bytes;
+
+/// Measures a text in Unicode scalar values, i.e. `char`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Chars;
+
+/// Measures a text in extended grapheme clusters, i.e. user-perceived characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Graphemes;
+
+/// Measures a text in whitespace-separated words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Words;
+
+impl Sizer for Bytes {
+    fn size(&self, text: &str) -> usize {
+        text.len()
+    }
+}
+
+impl Sizer for Chars {
+    fn size(&self, text: &str) -> usize {
+        text.chars().count()
+    }
+}
+
+impl Sizer for Graphemes {
+    fn size(&self, text: &str) -> usize {
+        text.graphemes(true).count()
+    }
+}
+
+impl Sizer for Words {
+    fn size(&self, text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+}
+
+impl<F> Sizer for F
+where
+    F: Fn(&str) -> usize,
+{
+    fn size(&self, text: &str) -> usize {
+        self(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin() {
+        let text = "## インストール\n\n\u{1F469}\u{200D}\u{1F4BB} Rust";
+        assert_eq!(Bytes.size(text), 39);
+        assert_eq!(Chars.size(text), 19);
+        assert_eq!(Graphemes.size(text), 17);
+        assert_eq!(Words.size(text), 4);
+        assert_eq!((|t: &str| t.lines().count()).size(text), 3);
+    }
+}
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use regex::Regex;
+
+/// Turn a heading into the anchor GitHub generates for it, without the suffix which tells duplicate
+/// headings apart
+///
+/// The heading is lowercased, spaces are replaced with hyphens, and anything but letters, marks,
+/// numbers, connector punctuation such as underscores, and hyphens is removed, as `github-slugger`
+/// does. Letters of any script are kept along with their combining marks, e.g. `インストール` or
+/// `हिन्दी`.
+///
+/// # Arguments
+///
+/// - `heading`: The plain text of the heading.
+///
+/// # Returns
+///
+/// The slug of the heading, which may be empty.
+///
+/// ```
+/// assert_eq!(
+///     markdown_split::slug("Installing `rustup` on Linux/macOS"),
+///     "installing-rustup-on-linuxmacos"
+/// );
+/// ```
+pub fn slug(heading: &str) -> String {
+    static REMOVED: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"[^\p{L}\p{M}\p{N}\p{Pc} -]").unwrap());
+    REMOVED
+        .replace_all(&heading.trim().to_lowercase(), "")
+        .replace(' ', "-")
+}
+
+/// Generates the slugs of the headings of a text in order, suffixing the slugs already taken with
+/// `-1`, `-2` and so on as GitHub does.
+#[derive(Debug, Default)]
+pub(crate) struct Slugger {
+    /// How many times each slug was generated, minus one.
+    occurrences: HashMap<String, usize>,
+}
+
+impl Slugger {
+    /// The unique slug of the next heading.
+    pub(crate) fn slug(&mut self, heading: &str) -> String {
+        let original = slug(heading);
+        let mut slug = original.clone();
+        while self.occurrences.contains_key(&slug) {
+            let count = self.occurrences.entry(original.clone()).or_default();
+            *count += 1;
+            slug = format!("{original}-{count}");
+        }
+        self.occurrences.insert(slug.clone(), 0);
+        slug
+    }
+
+    /// Take an explicit identifier, so that no later heading gets the same slug.
+    pub(crate) fn take(&mut self, id: &str) {
+        self.occurrences.entry(id.to_string()).or_default();
+    }
+}
+
+/// Split the plain text of a heading into the text itself and the identifier of a trailing
+/// `{#custom-id}` attribute, if any.
+pub(crate) fn custom_id(heading: &str) -> (&str, Option<&str>) {
+    static PATTERN: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\s*\{#([^\s{}]+)\}\s*$").unwrap());
+    match PATTERN.captures(heading) {
+        Some(captures) => {
+            (&heading[..captures.get(0).unwrap().start()], Some(captures.get(1).unwrap().as_str()))
+        }
+        None => (heading, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Hello, World!"), "hello-world");
+        assert_eq!(slug(" Foo -- Bar_baz "), "foo----bar_baz");
+        assert_eq!(
+            slug("LinuxとmacOSにrustupをインストールする"),
+            "linuxとmacosにrustupをインストールする"
+        );
+        assert_eq!(slug("更新及びアンインストール、その他"), "更新及びアンインストールその他");
+        assert_eq!(slug("🎉"), "");
+        // Combining marks are kept, e.g. the virama of Devanagari or a decomposed accent.
+        assert_eq!(slug("हिन्दी"), "हिन्दी");
+        assert_eq!(slug("Cafe\u{301} au lait"), "cafe\u{301}-au-lait");
+        assert_eq!(slug("ภาษาไทย"), "ภาษาไทย");
+        assert_eq!(slug("a‿b"), "a‿b");
+
+        let mut slugger = Slugger::default();
+        let slugs = ["A", "A", "A-1", "A", "B"].map(|h| slugger.slug(h));
+        assert_eq!(slugs, ["a", "a-1", "a-1-1", "a-2", "b"]);
+
+        slugger.take("c");
+        assert_eq!(slugger.slug("C"), "c-1");
+    }
+
+    #[test]
+    fn test_custom_id() {
+        assert_eq!(custom_id("Installation {#install}"), ("Installation", Some("install")));
+        assert_eq!(custom_id("Installation"), ("Installation", None));
+        assert_eq!(custom_id("{#a b}"), ("{#a b}", None));
+    }
+}
@@ -0,0 +1,274 @@
+use std::ops::Range;
+
+use markdown::{
+    mdast::{
+        Node,
+        Node::{Heading as HeadingNode, Root},
+    },
+    ParseOptions,
+};
+
+use crate::{
+    error::{Error, Result},
+    front_matter::{scan_front_matter, FrontMatter},
+    options::{Backend, SplitOptions},
+    scan,
+    slug::{custom_id, Slugger},
+    split::{bound, to_ast, validate, with_options, SplitPoint},
+};
+
+/// A heading of a markdown text, as found by a markdown parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Heading {
+    /// The range of the heading in the text, from the start of its line. Where it ends differs
+    /// between parsers, see [`heading_range`].
+    pub(crate) range: Range<usize>,
+    /// The depth of the heading, from `1` (h1) to `6` (h6).
+    pub(crate) depth: u8,
+    /// The plain text of the heading, including a trailing `{#custom-id}` if any.
+    pub(crate) text: String,
+    /// Whether the heading is nested in a block quote, a list or a footnote definition. Such a
+    /// heading is not split on by the headings of a backend, but still counts to make slugs
+    /// unique.
+    pub(crate) nested: bool,
+}
+
+/// The plain text and the anchor of a heading.
+#[derive(Debug, Clone)]
+pub(crate) struct Anchor {
+    /// The plain text of the heading, without its `{#custom-id}` attribute.
+    pub(crate) heading: String,
+    /// The identifier of the `{#custom-id}` attribute of the heading, if any.
+    pub(crate) custom_id: Option<String>,
+    /// The custom identifier of the heading, or the slug GitHub generates for it.
+    pub(crate) slug: String,
+}
+
+/// Finds the headings of a markdown text with the parser of `backend`, in order, whether nested or
+/// not. [`Backend::Scanner`] only finds the headings at the top level.
+///
+/// `options` configure the `markdown` crate. Other parsers follow CommonMark and GFM, and only
+/// look at the `frontmatter` construct, to skip a front matter.
+pub(crate) fn headings(
+    text: &str,
+    options: &ParseOptions,
+    backend: Backend,
+) -> Result<Vec<Heading>> {
+    match backend {
+        Backend::Mdast => Ok(ast_headings(&to_ast(text, options)?)),
+        Backend::Scanner => Ok(scan::headings(text, options)),
+        #[cfg(feature = "pulldown-cmark")]
+        Backend::PulldownCmark => Ok(crate::pulldown::headings(text, options)),
+        #[cfg(feature = "comrak")]
+        Backend::Comrak => Ok(crate::comrak::headings(text, options)),
+    }
+}
+
+/// The headings of the AST the `markdown` crate builds, in order.
+pub(crate) fn ast_headings(ast: &Node) -> Vec<Heading> {
+    fn traverse(node: &Node, nested: bool, headings: &mut Vec<Heading>) {
+        match node {
+            HeadingNode(heading) => {
+                if let Some(position) = &heading.position {
+                    headings.push(Heading {
+                        range: position.start.offset..position.end.offset,
+                        depth: heading.depth,
+                        text: node.to_string(),
+                        nested,
+                    });
+                }
+            }
+            Root(root) => root.children.iter().for_each(|c| traverse(c, false, headings)),
+            _ => node
+                .children()
+                .into_iter()
+                .flatten()
+                .for_each(|c| traverse(c, true, headings)),
+        }
+    }
+    let mut headings = vec![];
+    traverse(ast, false, &mut headings);
+    headings
+}
+
+/// The anchors of the headings of a text, all of which count to make slugs unique, including those
+/// not split on. Custom identifiers are taken first so that no slug collides with them.
+pub(crate) fn anchors(headings: &[Heading]) -> Vec<Anchor> {
+    let mut slugger = Slugger::default();
+    headings
+        .iter()
+        .filter_map(|h| custom_id(&h.text).1)
+        .for_each(|id| slugger.take(id));
+    headings
+        .iter()
+        .map(|h| {
+            let (heading, custom_id) = custom_id(&h.text);
+            let slug = custom_id.map_or_else(|| slugger.slug(heading), str::to_string);
+            let custom_id = custom_id.map(str::to_string);
+            Anchor { heading: heading.to_string(), custom_id, slug }
+        })
+        .collect()
+}
+
+/// The range of a heading without the indentation it starts with nor the whitespace it ends with,
+/// whatever the parser includes in it.
+pub(crate) fn heading_range(text: &str, range: &Range<usize>) -> Range<usize> {
+    let heading = &text[range.clone()];
+    let start = range.start + (heading.len() - heading.trim_start_matches([' ', '\t']).len());
+    start..start.max(range.start + heading.trim_end().len())
+}
+
+/// Find where the sections of a markdown text start from the headings `backend` finds, followed by
+/// the end of the text, along with its front matter if [`SplitOptions::front_matter`] is set.
+///
+/// Unlike the split points found in the AST, these are the same whatever the parser, but do not
+/// support the options which need more than the headings.
+pub(crate) fn split_points<'a>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+    backend: Backend,
+) -> Result<(Option<FrontMatter<'a>>, Vec<SplitPoint>)> {
+    validate(text, split_options)?;
+    let unsupported = [
+        (!split_options.markers.is_empty(), "markers"),
+        (split_options.comments, "comments"),
+        (split_options.nested, "nested"),
+        (split_options.definitions, "definitions"),
+        (split_options.footnotes, "footnotes"),
+    ];
+    if let Some((_, option)) = unsupported.into_iter().find(|(set, _)| *set) {
+        return Err(Error::Unsupported { backend, option });
+    }
+    if text.is_empty() {
+        return Ok((None, vec![]));
+    }
+
+    let headings =
+        with_options(options, split_options, |options| headings(text, options, backend))?;
+    let front_matter = split_options.front_matter.then(|| scan_front_matter(text)).flatten();
+    let start = front_matter.as_ref().map_or(0, |f| f.range.end);
+    // A parser which does not know a kind of front matter may find a heading in it.
+    let headings = headings
+        .into_iter()
+        .filter(|h| h.range.start >= start)
+        .collect::<Vec<_>>();
+
+    let split_points = headings
+        .iter()
+        .zip(anchors(&headings))
+        .filter(|(h, _)| !h.nested && split_options.splits_on(h.depth))
+        .map(|(h, anchor)| SplitPoint::heading(text, h.range.start, h, anchor))
+        .collect();
+
+    Ok((front_matter, bound(split_points, start, text.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+    use crate::split::split_sections_with;
+
+    #[test]
+    fn test_anchors() {
+        // Headings not split on, nested or not, count to make slugs unique.
+        let text = "# A\n\n> # A\n\n# A\n\nIntro\n   # B #  \n";
+        let sections = split_sections_with(text, None, &SplitOptions::default()).unwrap();
+        assert_eq!(
+            sections
+                .iter()
+                .map(|s| (s.slug.as_deref(), s.heading_range.clone()))
+                .collect::<Vec<_>>(),
+            [(Some("a"), Some(0..3)), (Some("a-2"), Some(12..15)), (Some("b"), Some(26..31))]
+        );
+    }
+
+    #[test]
+    fn test_split_points() {
+        // The split points built from the headings of the AST are those found in the AST itself.
+        for language in ["en", "ja"] {
+            let text = read_to_string(format!("tests/fixtures/ch01-01-installation.{language}.md"))
+                .unwrap();
+            let options = SplitOptions { max_depth: 2, ..Default::default() };
+            let (_, points) = split_points(&text, None, &options, Backend::Mdast).unwrap();
+            let sections = split_sections_with(&text, None, &options).unwrap();
+            assert_eq!(
+                points.iter().map(|p| p.offset).collect::<Vec<_>>(),
+                sections
+                    .iter()
+                    .map(|s| s.range.start)
+                    .chain([text.len()])
+                    .collect::<Vec<_>>()
+            );
+            assert!(points
+                .iter()
+                .zip(&sections)
+                .all(|(p, s)| p.heading == s.heading && p.slug == s.slug));
+        }
+    }
+
+    /// The split points `backend` finds in the fixtures and in edge cases are those found in the
+    /// AST.
+    #[cfg(any(feature = "pulldown-cmark", feature = "comrak"))]
+    fn assert_same_as_mdast(backend: Backend) {
+        let fields = |points: Vec<SplitPoint>| {
+            points
+                .into_iter()
+                .map(|p| (p.offset, p.depth, p.heading, p.slug, p.heading_range))
+                .collect::<Vec<_>>()
+        };
+        let fixtures = ["en", "ja"].map(|language| {
+            read_to_string(format!("tests/fixtures/ch01-01-installation.{language}.md")).unwrap()
+        });
+        // A nested heading counts to make slugs unique, and the range of an indented heading with
+        // trailing spaces is trimmed.
+        let cases = ["# A\n\n> # A\n\n# A\n", "Intro\n   # A #  \n"];
+        for text in fixtures.iter().map(String::as_str).chain(cases) {
+            let options = SplitOptions::default();
+            let (_, expected) = split_points(text, None, &options, Backend::Mdast).unwrap();
+            let (_, actual) = split_points(text, None, &options, backend).unwrap();
+            assert_eq!(fields(actual), fields(expected));
+        }
+
+        let text = "---\ntitle: A\n---\n# A *b* `c`\n\n> # D\n\nE\n===\n";
+        let options = SplitOptions { front_matter: true, ..Default::default() };
+        let (front_matter, points) = split_points(text, None, &options, backend).unwrap();
+        assert_eq!(front_matter.map(|f| f.range), Some(0..17));
+        assert_eq!(
+            points
+                .iter()
+                .map(|p| (p.offset, p.heading.as_deref()))
+                .collect::<Vec<_>>(),
+            [(17, Some("A b c")), (37, Some("E")), (text.len(), None)]
+        );
+    }
+
+    #[cfg(feature = "pulldown-cmark")]
+    #[test]
+    fn test_pulldown_cmark() {
+        assert_same_as_mdast(Backend::PulldownCmark);
+    }
+
+    #[cfg(feature = "comrak")]
+    #[test]
+    fn test_comrak() {
+        assert_same_as_mdast(Backend::Comrak);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let options = SplitOptions {
+            comments: true,
+            backend: Backend::Scanner,
+            ..Default::default()
+        };
+        let result = split_sections_with("# A\n", None, &options);
+        assert!(matches!(result, Err(Error::Unsupported { option: "comments", .. })));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "The Scanner backend does not support the `comments` option"
+        );
+    }
+}
@@ -1,12 +1,30 @@
-use anyhow::{anyhow, Result};
-use itertools::Itertools;
+use std::{
+    collections::HashMap,
+    ops::Range,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
 use log::debug;
 use markdown::{
+    mdast,
     mdast::{
         Node,
-        Node::{Heading, Root},
+        Node::{Blockquote, Heading, Html, List, ListItem, MdxJsxFlowElement, Root},
     },
-    to_mdast, ParseOptions,
+    message::Place,
+    to_mdast, Constructs, ParseOptions,
+};
+
+use crate::{
+    error::{Error, Result},
+    front_matter::find_front_matter,
+    iter::{split_iter, SectionIter},
+    location::LineIndex,
+    options::{Backend, SplitOptions},
+    references::References,
+    section::{Document, Section},
+    source,
+    source::{anchors, ast_headings, heading_range, Anchor},
 };
 
 /// Split a markdown text into sections based on headings
@@ -26,60 +44,364 @@ use markdown::{
 /// Returns an error if the markdown text is empty or if the text cannot be parsed by the `markdown`
 /// crate.
 pub fn split<'a>(text: &'a str, options: Option<&ParseOptions>) -> Result<Vec<&'a str>> {
-    if text.is_empty() {
-        return Err(anyhow!("The input text is empty"));
-    }
+    split_with(text, options, &SplitOptions::default())
+}
 
-    let options = if let Some(o) = options { o } else { &ParseOptions::gfm() };
-    let ast = to_mdast(text, options).map_err(|e| anyhow!("{e}"))?;
-    let mut split_points = find_split_points(&ast);
+/// Split a markdown text into sections based on headings, as configured by `split_options`
+///
+/// See [`split`] for the arguments and the return value.
+///
+/// # Errors
+///
+/// Returns an error if the markdown text is empty, if the depth range in `split_options` is
+/// invalid, or if the text cannot be parsed by the `markdown` crate.
+pub fn split_with<'a>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+) -> Result<Vec<&'a str>> {
+    Ok(split_sections_with(text, options, split_options)?
+        .into_iter()
+        .map(|s| s.text)
+        .collect())
+}
 
-    // The very last split point is always the end of the text.
-    split_points.push(text.len());
-    debug!("Split points: {split_points:?}");
+/// Split a markdown text into sections based on headings, keeping what is known about each section
+///
+/// # Arguments
+///
+/// - `text`: A string slice containing the markdown text to split.
+/// - `options`: An optional `ParseOptions` struct to configure the markdown parser. If `None`,
+///   `ParseOptions::gfm()` (GitHub Flavored Markdown) is used.
+///
+/// # Returns
+///
+/// A vector of [`Section`]s, each borrowing a slice of the original markdown text along with its
+/// heading depth, heading text, byte range and start location.
+///
+/// # Errors
+///
+/// Returns an error if the markdown text is empty or if the text cannot be parsed by the `markdown`
+/// crate.
+pub fn split_sections<'a>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+) -> Result<Vec<Section<'a>>> {
+    split_sections_with(text, options, &SplitOptions::default())
+}
 
-    let sections: Vec<&str> = split_points
-        .iter()
-        .tuple_windows()
-        .map(|(start, end)| &text[*start..*end])
-        .collect::<_>();
+/// Split a markdown text into [`Section`]s based on headings, as configured by `split_options`
+///
+/// See [`split_sections`] for the arguments and the return value.
+///
+/// # Errors
+///
+/// Returns an error if the markdown text is empty, if the depth range in `split_options` is
+/// invalid, or if the text cannot be parsed by the `markdown` crate.
+pub fn split_sections_with<'a>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+) -> Result<Vec<Section<'a>>> {
+    let sections = split_iter(text, options, split_options)?.collect::<Vec<_>>();
     debug!("Found {} sections", sections.len());
 
     Ok(sections)
 }
 
-/// Find the offsets of headings in an AST, and use them as split points for the text.
-fn find_split_points(node: &Node) -> Vec<usize> {
-    let mut split_points = vec![];
+/// Split a markdown text into its front matter and its [`Section`]s, as configured by
+/// `split_options`
+///
+/// The front matter is only looked for if [`SplitOptions::front_matter`] is set. See
+/// [`split_sections`] for the arguments.
+///
+/// # Errors
+///
+/// Returns an error if the markdown text is empty, if the depth range in `split_options` is
+/// invalid, or if the text cannot be parsed by the `markdown` crate.
+pub fn split_document<'a>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+) -> Result<Document<'a>> {
+    let (front_matter, sections) = match split_options.backend {
+        Backend::Mdast => {
+            let ast = parse(text, options, split_options)?;
+            let front_matter = split_options
+                .front_matter
+                .then(|| find_front_matter(text, &ast))
+                .flatten();
+            (front_matter, sections_from_ast(text, &ast, split_options))
+        }
+        backend => {
+            let (front_matter, points) =
+                source::split_points(text, options, split_options, backend)?;
+            (front_matter, SectionIter::from_points(text, points, References::none(text)).collect())
+        }
+    };
+    debug!("Found {} sections", sections.len());
 
-    fn traverse(node: &Node, split_points: &mut Vec<usize>) {
-        match node {
-            Root(root) => {
-                root.children.iter().for_each(|c| traverse(c, split_points));
-            }
-            Heading(heading) if heading.position.as_ref().is_some() => {
-                split_points.push(heading.position.as_ref().unwrap().start.offset);
+    Ok(Document { front_matter, sections })
+}
+
+/// Validate the input and the options, and parse the markdown text into an AST.
+pub(crate) fn parse(
+    text: &str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+) -> Result<Node> {
+    validate(text, split_options)?;
+    with_options(options, split_options, |options| to_ast(text, options))
+}
+
+/// Call `f` with the options to parse a text with: `options`, or `ParseOptions::gfm()` if `None`,
+/// with the `frontmatter` construct enabled if [`SplitOptions::front_matter`] is set.
+pub(crate) fn with_options<T>(
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+    f: impl FnOnce(&ParseOptions) -> T,
+) -> T {
+    let options = if let Some(o) = options { o } else { &ParseOptions::gfm() };
+    if split_options.front_matter && !options.constructs.frontmatter {
+        // `ParseOptions` cannot be cloned because of the MDX hooks, which are not needed to find
+        // the front matter anyway.
+        let options = ParseOptions {
+            constructs: Constructs { frontmatter: true, ..options.constructs.clone() },
+            gfm_strikethrough_single_tilde: options.gfm_strikethrough_single_tilde,
+            math_text_single_dollar: options.math_text_single_dollar,
+            ..ParseOptions::default()
+        };
+        return f(&options);
+    }
+    f(options)
+}
+
+/// Check that the text may be split as configured by `split_options`, whatever the backend.
+pub(crate) fn validate(text: &str, split_options: &SplitOptions) -> Result<()> {
+    if text.is_empty() && !split_options.allow_empty {
+        return Err(Error::EmptyInput);
+    }
+    let SplitOptions { min_depth, max_depth, .. } = *split_options;
+    if min_depth < 1 || max_depth > 6 || min_depth > max_depth {
+        return Err(Error::InvalidDepthRange { min: min_depth, max: max_depth });
+    }
+    Ok(())
+}
+
+/// Parse a markdown text into an AST, turning a panic of the parser into an error.
+pub(crate) fn to_ast(text: &str, options: &ParseOptions) -> Result<Node> {
+    // The `markdown` crate panics on some inputs instead of returning an error, e.g. on a link
+    // reference definition directly followed by a setext heading underline (`[a]: b\n---\nx\n---`.)
+    catch_unwind(AssertUnwindSafe(|| to_mdast(text, options)))
+        .map_err(|_| Error::ParserPanic)?
+        .map_err(|e| {
+            let offset = e.place.map(|place| match *place {
+                Place::Position(position) => position.start.offset,
+                Place::Point(point) => point.offset,
+            });
+            Error::Parse {
+                message: e.reason,
+                position: offset.map(|offset| LineIndex::new(text).location(offset)),
             }
-            _ => {}
-        }
+        })
+}
+
+/// Slice a markdown text into sections at the split points found in its AST.
+pub(crate) fn sections_from_ast<'a>(
+    text: &'a str,
+    ast: &Node,
+    split_options: &SplitOptions,
+) -> Vec<Section<'a>> {
+    SectionIter::new(text, ast, split_options).collect()
+}
+
+/// Find where the sections of a markdown text start in its AST, followed by the end of the text.
+pub(crate) fn split_points(
+    text: &str,
+    ast: &Node,
+    split_options: &SplitOptions,
+) -> Vec<SplitPoint> {
+    if text.is_empty() {
+        return vec![];
     }
-    traverse(node, &mut split_points);
+    let start = match split_options.front_matter {
+        true => find_front_matter(text, ast).map_or(0, |f| f.range.end),
+        false => 0,
+    };
+    bound(find_split_points(text, ast, split_options), start, text.len())
+}
 
-    // The very first split point should always be 0 (the start of the text.)
+/// Add the bounds of the sections to the split points found in a text: `start` (the start of the
+/// text, or the end of the front matter) first, unless a section starts there already, and `end`
+/// last.
+pub(crate) fn bound(
+    mut split_points: Vec<SplitPoint>,
+    start: usize,
+    end: usize,
+) -> Vec<SplitPoint> {
     match split_points.first() {
-        Some(&first) if first != 0 => split_points.insert(0, 0),
-        None => split_points.push(0),
+        Some(point) if point.offset != start => split_points.insert(0, SplitPoint::bare(start)),
+        None => split_points.push(SplitPoint::bare(start)),
         _ => { /* Keep it as is */ }
     }
+    split_points.push(SplitPoint::bare(end));
+    debug!("Split points: {:?}", split_points.iter().map(|p| p.offset).collect::<Vec<_>>());
+
+    split_points
+}
+
+/// A position in the text where a new section starts.
+#[derive(Debug, Clone)]
+pub(crate) struct SplitPoint {
+    pub(crate) offset: usize,
+    pub(crate) depth: Option<u8>,
+    pub(crate) heading: Option<String>,
+    pub(crate) id: Option<String>,
+    pub(crate) slug: Option<String>,
+    pub(crate) heading_range: Option<Range<usize>>,
+}
+
+impl SplitPoint {
+    /// A split point without a heading, i.e. the start or the end of the text.
+    pub(crate) fn bare(offset: usize) -> Self {
+        Self {
+            offset,
+            depth: None,
+            heading: None,
+            id: None,
+            slug: None,
+            heading_range: None,
+        }
+    }
+
+    /// A split point at `offset` for a heading of `text`, the anchor of which is `anchor`.
+    pub(crate) fn heading(
+        text: &str,
+        offset: usize,
+        heading: &source::Heading,
+        anchor: Anchor,
+    ) -> Self {
+        Self {
+            offset,
+            depth: Some(heading.depth),
+            heading: Some(anchor.heading),
+            id: anchor.custom_id,
+            slug: Some(anchor.slug),
+            heading_range: Some(heading_range(text, &heading.range)),
+        }
+    }
+}
+
+/// Find the offsets of headings within the configured depth range and of markers in an AST, and use
+/// them as split points for the text.
+fn find_split_points(text: &str, node: &Node, options: &SplitOptions) -> Vec<SplitPoint> {
+    let mut split_points = vec![];
+    let headings = ast_headings(node);
+    let anchors = anchors(&headings);
+    let headings = headings
+        .iter()
+        .zip(anchors)
+        .map(|(h, anchor)| (h.range.start, (h, anchor)))
+        .collect::<HashMap<_, _>>();
+
+    fn traverse(
+        node: &Node,
+        previous: Option<&Node>,
+        text: &str,
+        options: &SplitOptions,
+        headings: &HashMap<usize, (&source::Heading, Anchor)>,
+        split_points: &mut Vec<SplitPoint>,
+        after_marker: &mut bool,
+    ) {
+        if let Root(root) = node {
+            let previous = [None].into_iter().chain(root.children.iter().map(Some));
+            root.children.iter().zip(previous).for_each(|(c, previous)| {
+                traverse(c, previous, text, options, headings, split_points, after_marker)
+            });
+            return;
+        }
+
+        match (node.position(), find_heading(node, options)) {
+            // The section of a heading nested in a container starts with the container, so that
+            // its slice remains valid markdown.
+            (Some(position), Some(heading)) => {
+                let (heading, anchor) = &headings[&heading.position.as_ref().unwrap().start.offset];
+                match split_points.last_mut() {
+                    // A heading right after a marker is the heading of the marker's section.
+                    Some(marker) if *after_marker => {
+                        *marker = SplitPoint::heading(text, marker.offset, heading, anchor.clone());
+                    }
+                    _ => {
+                        // A comment right before the heading may start the heading's section, and
+                        // tell its original heading if the text is a translation.
+                        let comment = previous
+                            .filter(|_| options.comments)
+                            .and_then(|p| Some((&p.position()?.start, html_comment(p)?)));
+                        let (start, id) = match comment {
+                            Some((start, comment)) => (start.offset, heading_in_comment(comment)),
+                            None => (position.start.offset, None),
+                        };
+                        let mut split_point =
+                            SplitPoint::heading(text, start, heading, anchor.clone());
+                        split_point.id = split_point.id.or(id);
+                        split_points.push(split_point);
+                    }
+                }
+                *after_marker = false;
+            }
+            (Some(position), None) if options.markers.iter().any(|m| m.matches(node)) => {
+                split_points.push(SplitPoint::bare(position.start.offset));
+                *after_marker = true;
+            }
+            _ => *after_marker = false,
+        }
+    }
+    traverse(node, None, text, options, &headings, &mut split_points, &mut false);
 
     split_points
 }
 
+/// The heading to split on at a top-level block: the block itself, or with
+/// [`SplitOptions::nested`], the first heading nested in it if it is a container.
+fn find_heading<'n>(node: &'n Node, options: &SplitOptions) -> Option<&'n mdast::Heading> {
+    match node {
+        Heading(heading) if heading.position.is_some() && options.splits_on(heading.depth) => {
+            Some(heading)
+        }
+        Blockquote(_) | List(_) | ListItem(_) | MdxJsxFlowElement(_) if options.nested => {
+            node.children()?.iter().find_map(|c| find_heading(c, options))
+        }
+        _ => None,
+    }
+}
+
+/// The content of an HTML node if it is a comment, without its delimiters.
+pub(crate) fn html_comment(node: &Node) -> Option<&str> {
+    match node {
+        Html(html) => html.value.trim().strip_prefix("<!--")?.strip_suffix("-->"),
+        _ => None,
+    }
+}
+
+/// The plain text of the heading in the content of a comment, if it contains a single heading and
+/// nothing else, e.g. `### Installing `rustup` on Windows`. A comment the `markdown` crate cannot
+/// parse, or panics on, has no heading.
+pub(crate) fn heading_in_comment(comment: &str) -> Option<String> {
+    let Root(root) = to_ast(comment.trim(), &ParseOptions::default()).ok()? else {
+        return None;
+    };
+    match root.children.as_slice() {
+        [heading @ Heading(_)] => Some(heading.to_string()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::read_to_string;
 
     use super::*;
+    use crate::{front_matter::FrontMatterKind, location::Location, options::Marker};
 
     #[test]
     fn test_en() {
@@ -554,6 +876,12 @@ sure what it does or how to use it, use the application programming interface
         let result = split(text, None);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().to_string(), "The input text is empty");
+        assert!(matches!(split(text, None), Err(Error::EmptyInput)));
+
+        let options = SplitOptions { allow_empty: true, ..Default::default() };
+        assert_eq!(split_with(text, None, &options).unwrap(), Vec::<&str>::new());
+        let document = split_document(text, None, &options).unwrap();
+        assert!(document.front_matter.is_none() && document.sections.is_empty());
     }
 
     #[test]
@@ -566,4 +894,330 @@ Lorem ipsum dolor sit amet, consectetur adipiscing elit. Sed do eiusmod tempor i
         assert_eq!(sections.len(), 1);
         assert_eq!(sections[0], text);
     }
+
+    #[test]
+    fn test_sections() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.en.md").unwrap();
+
+        let sections = split_sections(&text, None).unwrap();
+        assert_eq!(sections.len(), 7);
+
+        assert_eq!(sections[0].depth, None);
+        assert_eq!(sections[0].heading, None);
+        assert_eq!(sections[0].range.start, 0);
+        assert_eq!(sections[0].start, Location { line: 1, ..Default::default() });
+
+        assert_eq!(sections[2].depth, Some(3));
+        assert_eq!(sections[2].heading.as_deref(), Some("Installing rustup on Linux or macOS"));
+        assert_eq!(sections[2].start, Location { line: 28, ..Default::default() });
+        assert_eq!(sections[2].path, vec!["Installation", "Installing rustup on Linux or macOS"]);
+        assert!(sections[0].path.is_empty());
+        assert_eq!(&text[sections[2].range.clone()], sections[2].text);
+
+        assert_eq!(sections[6].range.end, text.len());
+        assert_eq!(
+            sections.iter().map(|s| s.text).collect::<Vec<_>>(),
+            split(&text, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_depth_range() {
+        let text = "Intro\n\n# A\n\n## B\n\n### C\n\n## D\n";
+
+        let options = SplitOptions { min_depth: 1, max_depth: 2, ..Default::default() };
+        let sections = split_with(text, None, &options).unwrap();
+        assert_eq!(sections, vec!["Intro\n\n", "# A\n\n", "## B\n\n### C\n\n", "## D\n"]);
+
+        let options = SplitOptions { min_depth: 2, max_depth: 3, ..Default::default() };
+        let sections = split_with(text, None, &options).unwrap();
+        assert_eq!(sections, vec!["Intro\n\n# A\n\n", "## B\n\n", "### C\n\n", "## D\n"]);
+
+        let options = SplitOptions { min_depth: 3, max_depth: 2, ..Default::default() };
+        let result = split_with(text, None, &options);
+        assert_eq!(result.unwrap_err().to_string(), "Invalid heading depth range: 3..=2");
+        assert!(matches!(
+            split_with(text, None, &options),
+            Err(Error::InvalidDepthRange { min: 3, max: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_path() {
+        let text = "# A\n\n### B\n\n## C\n\n# D\n";
+
+        let sections = split_sections(text, None).unwrap();
+        assert_eq!(
+            sections.iter().map(|s| s.path.join("/")).collect::<Vec<_>>(),
+            vec!["A", "A/B", "A/C", "D"]
+        );
+    }
+
+    #[test]
+    fn test_definitions() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.en.md").unwrap();
+
+        let options = SplitOptions { definitions: true, ..Default::default() };
+        let sections = split_sections_with(&text, None, &options).unwrap();
+        assert_eq!(
+            sections[1].definitions,
+            vec![
+                "[otherinstall]: https://forge.rust-lang.org/infra/other-installation-methods.html"
+            ]
+        );
+        assert_eq!(
+            sections[3].definitions,
+            vec![
+                "[install]: https://www.rust-lang.org/tools/install",
+                "[msvc]: https://rust-lang.github.io/rustup/installation/windows-msvc.html",
+            ]
+        );
+        assert!(sections[6].definitions.is_empty());
+        assert!(sections[3].with_definitions().ends_with(
+            r#"If there are specific differences, we’ll explain which to use.
+
+[install]: https://www.rust-lang.org/tools/install
+[msvc]: https://rust-lang.github.io/rustup/installation/windows-msvc.html
+"#
+        ));
+
+        let sections = split_sections(&text, None).unwrap();
+        assert!(sections.iter().all(|s| s.definitions.is_empty()));
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let text = "# A\n\nSee[^1] and [this][a].\n\n# B\n\nAgain[^1], and[^2].\n\n[^2]: Two.\n\n# Notes\n\n[^1]: One\n    continued.\n\n[a]: https://example.com\n";
+
+        let options = SplitOptions { footnotes: true, ..Default::default() };
+        let sections = split_sections_with(text, None, &options).unwrap();
+        assert_eq!(sections[0].footnotes, vec!["[^1]: One\n    continued."]);
+        assert_eq!(sections[1].footnotes, vec!["[^1]: One\n    continued."]);
+        assert!(sections[2].footnotes.is_empty());
+        assert!(sections.iter().all(|s| s.definitions.is_empty()));
+        assert_eq!(
+            sections[0].with_footnotes(),
+            "# A\n\nSee[^1] and [this][a].\n\n[^1]: One\n    continued.\n"
+        );
+    }
+
+    #[test]
+    fn test_markers() {
+        let text = "Title\n=====\n\nIntro\n\n---\n\n# One\n\n1\n\n---\n\nNo heading\n\n<!-- split -->\n\nSub\n---\n";
+
+        let sections = split(text, None).unwrap();
+        assert_eq!(
+            sections,
+            vec![
+                "Title\n=====\n\nIntro\n\n---\n\n",
+                "# One\n\n1\n\n---\n\nNo heading\n\n<!-- split -->\n\n",
+                "Sub\n---\n"
+            ]
+        );
+
+        let options = SplitOptions {
+            markers: vec![Marker::ThematicBreak, Marker::html_comment("^split$").unwrap()],
+            ..Default::default()
+        };
+        let sections = split_sections_with(text, None, &options).unwrap();
+        assert_eq!(
+            sections.iter().map(|s| s.text).collect::<Vec<_>>(),
+            vec![
+                "Title\n=====\n\nIntro\n\n",
+                "---\n\n# One\n\n1\n\n",
+                "---\n\nNo heading\n\n",
+                "<!-- split -->\n\nSub\n---\n",
+            ]
+        );
+        assert_eq!(
+            sections.iter().map(|s| s.heading.as_deref()).collect::<Vec<_>>(),
+            vec![Some("Title"), Some("One"), None, Some("Sub")]
+        );
+        assert_eq!(sections[2].path, vec!["One"]);
+
+        let options = SplitOptions {
+            headings: false,
+            markers: vec![Marker::custom(|node| matches!(node, Node::Paragraph(_)))],
+            ..Default::default()
+        };
+        let sections = split_with(text, None, &options).unwrap();
+        assert_eq!(sections.len(), 4);
+        assert_eq!(sections[1], "Intro\n\n---\n\n# One\n\n");
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let text = "---\ntitle: Installation\ntags: [rust]\n---\n\nIntro\n\n# A\n";
+
+        let options = SplitOptions { front_matter: true, ..Default::default() };
+        let document = split_document(text, None, &options).unwrap();
+        let front_matter = document.front_matter.unwrap();
+        assert_eq!(front_matter.kind, FrontMatterKind::Yaml);
+        assert_eq!(front_matter.text, "---\ntitle: Installation\ntags: [rust]\n---\n\n");
+        assert_eq!(front_matter.raw, "title: Installation\ntags: [rust]");
+        assert_eq!(
+            document.sections.iter().map(|s| s.text).collect::<Vec<_>>(),
+            vec!["Intro\n\n", "# A\n"]
+        );
+        assert_eq!(document.sections[0].range, 42..49);
+        assert_eq!(document.sections[0].start, Location { line: 6, ..Default::default() });
+
+        let text = "+++\ntitle = \"Installation\"\n+++\n# A\n";
+        let document = split_document(text, None, &options).unwrap();
+        assert_eq!(document.front_matter.unwrap().kind, FrontMatterKind::Toml);
+        assert_eq!(split_with(text, None, &options).unwrap(), vec!["# A\n"]);
+
+        let sections = split(text, None).unwrap();
+        assert_eq!(sections, vec!["+++\ntitle = \"Installation\"\n+++\n", "# A\n"]);
+        let document = split_document(text, None, &SplitOptions::default()).unwrap();
+        assert_eq!(document.front_matter, None);
+    }
+
+    #[cfg(feature = "front-matter")]
+    #[test]
+    fn test_front_matter_deserialize() {
+        #[derive(serde::Deserialize)]
+        struct Meta {
+            title: String,
+        }
+
+        let options = SplitOptions { front_matter: true, ..Default::default() };
+        let text = "---\ntitle: Installation\n---\n# A\n";
+        let document = split_document(text, None, &options).unwrap();
+        let meta: Meta = document.front_matter.unwrap().deserialize().unwrap();
+        assert_eq!(meta.title, "Installation");
+
+        let text = "+++\ntitle = \"Installation\"\n+++\n# A\n";
+        let document = split_document(text, None, &options).unwrap();
+        let meta: Meta = document.front_matter.unwrap().deserialize().unwrap();
+        assert_eq!(meta.title, "Installation");
+    }
+
+    #[test]
+    fn test_parser_panic() {
+        let result = split("[a]: b\n---\nx\n---", None);
+        assert!(matches!(result, Err(Error::ParserPanic)));
+        assert_eq!(result.unwrap_err().to_string(), "The markdown parser panicked");
+
+        // The content of a comment is parsed again on its own, where the parser may panic.
+        let text = "<!--\n[a]: b\n---\nx\n---\n-->\n# H\n";
+        let options = SplitOptions { comments: true, ..Default::default() };
+        let sections = split_sections_with(text, None, &options).unwrap();
+        assert_eq!(
+            sections
+                .iter()
+                .map(|s| (s.heading.as_deref(), s.id.as_deref()))
+                .collect::<Vec<_>>(),
+            [(Some("H"), None)]
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.ja.md").unwrap();
+
+        let options = SplitOptions { comments: true, ..Default::default() };
+        let sections = split_sections_with(&text, None, &options).unwrap();
+        assert_eq!(sections.len(), 7);
+        assert_eq!(
+            sections[0].text,
+            "<!-- This is from https://github.com/rust-lang-ja/book-ja/blob/822ffbb7b5ecf28ff5393e4057c8b9189a5d3fe1/src/ch01-01-installation.md -->\n\n"
+        );
+        assert!(sections[1]
+            .text
+            .starts_with("<!--\n## Installation\n-->\n\n## インストール\n"));
+        assert_eq!(sections[1].start, Location { line: 3, ..Default::default() });
+        assert!(sections[1].text.ends_with("> `$`ではなく、`>`を使用します。\n\n"));
+        assert_eq!(
+            sections.iter().map(|s| s.id.as_deref()).collect::<Vec<_>>(),
+            vec![
+                None,
+                Some("Installation"),
+                Some("Installing rustup on Linux or macOS"),
+                Some("Installing rustup on Windows"),
+                Some("Updating and Uninstalling"),
+                Some("Troubleshooting"),
+                Some("Local Documentation"),
+            ]
+        );
+        assert_eq!(sections[5].heading.as_deref(), Some("トラブルシューティング"));
+
+        let sections = split_sections(&text, None).unwrap();
+        assert!(sections.iter().all(|s| s.id.is_none()));
+    }
+
+    #[test]
+    fn test_slugs() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.ja.md").unwrap();
+        let sections = split_sections(&text, None).unwrap();
+        assert_eq!(
+            sections.iter().map(|s| s.slug.as_deref()).collect::<Vec<_>>(),
+            vec![
+                None,
+                Some("インストール"),
+                Some("linuxとmacosにrustupをインストールする"),
+                Some("windowsでrustupをインストールする"),
+                Some("更新及びアンインストール"),
+                Some("トラブルシューティング"),
+                Some("ローカルのドキュメンテーション"),
+            ]
+        );
+
+        let text = "# A\n\n## Setup\n\n### B\n\n# A\n\n## Setup {#setup-1}\n\n# Setup\n";
+        let options = SplitOptions { max_depth: 2, ..Default::default() };
+        let sections = split_sections_with(text, None, &options).unwrap();
+        assert_eq!(
+            sections
+                .iter()
+                .map(|s| s.slug.as_deref().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["a", "setup", "a-1", "setup-1", "setup-2"]
+        );
+        assert_eq!(sections[3].heading.as_deref(), Some("Setup"));
+        assert_eq!(sections[3].id.as_deref(), Some("setup-1"));
+        assert_eq!(sections[3].path, vec!["A", "Setup"]);
+        assert_eq!(sections[1].id, None);
+    }
+
+    #[test]
+    fn test_nested() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.en.md").unwrap();
+
+        let sections = split_sections(&text, None).unwrap();
+        assert_eq!(sections.len(), 7);
+        assert!(sections[1].text.contains("> ### Command Line Notation"));
+
+        let options = SplitOptions { nested: true, ..Default::default() };
+        let sections = split_sections_with(&text, None, &options).unwrap();
+        assert_eq!(sections.len(), 8);
+        assert!(sections[2].text.starts_with("> ### Command Line Notation\n>\n"));
+        assert_eq!(sections[2].heading.as_deref(), Some("Command Line Notation"));
+        assert_eq!(sections[2].depth, Some(3));
+        assert_eq!(sections[2].path, vec!["Installation", "Command Line Notation"]);
+        assert_eq!(sections[2].start, Location { line: 19, ..Default::default() });
+
+        // Only the first heading of a top-level block splits, at the start of the block.
+        let text = "Intro\n\n- a\n- b\n\n  ## One\n\n  ## Two\n\n> > # Deep\n";
+        let sections = split_with(text, None, &options).unwrap();
+        assert_eq!(
+            sections,
+            vec!["Intro\n\n", "- a\n- b\n\n  ## One\n\n  ## Two\n\n", "> > # Deep\n"]
+        );
+        let options = SplitOptions { nested: true, max_depth: 1, ..Default::default() };
+        assert_eq!(
+            split_with(text, None, &options).unwrap(),
+            vec![text.strip_suffix("> > # Deep\n").unwrap(), "> > # Deep\n"]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let result = split("# Title\n\n- 🦀 <b !>\n\nText\n", Some(&ParseOptions::mdx()));
+        let Err(Error::Parse { message, position }) = result else { panic!("{result:?}") };
+        assert!(!message.is_empty());
+        assert_eq!(
+            position.map(|p| (p.line, p.column, p.utf8_column, p.utf16_column)),
+            Some((3, 8, 11, 9))
+        );
+    }
 }
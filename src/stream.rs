@@ -0,0 +1,549 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+    ops::Range,
+};
+
+use markdown::{
+    mdast::{
+        Node,
+        Node::{Code, Root},
+    },
+    ParseOptions,
+};
+
+use crate::{
+    error::Result,
+    location::Location,
+    options::{Marker, SplitOptions},
+    section::Section,
+    split::{parse, sections_from_ast},
+};
+
+/// A section of a markdown text read from a stream, which owns its text.
+///
+/// Unlike [`Section`], it has no slug nor reference definitions, which depend on the whole text.
+/// For the same reason, the plain text of a heading which uses a link reference, e.g. `# [a]`, may
+/// differ if the reference is defined after the heading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OwnedSection {
+    /// The text of this section, including the heading.
+    pub text: String,
+    /// The depth of the heading (`1` for h1 to `6` for h6), or `None` for the text before the
+    /// first heading.
+    pub depth: Option<u8>,
+    /// The plain text of the heading, or `None` for the text before the first heading.
+    pub heading: Option<String>,
+    /// The plain text of the headings of the enclosing sections and of this section, from the
+    /// outermost to this one.
+    pub path: Vec<String>,
+    /// The byte range of this section in the whole stream.
+    pub range: Range<usize>,
+    /// The location where this section starts in the whole stream.
+    pub start: Location,
+    /// The location where this section ends in the whole stream, exclusive.
+    pub end: Location,
+}
+
+impl OwnedSection {
+    /// Copy a section of a text which starts at `offset` in the whole stream, after `lines` lines.
+    fn new(section: Section, offset: usize, lines: usize) -> Self {
+        let shift = |location: Location| Location { line: location.line + lines, ..location };
+        Self {
+            text: section.text.to_string(),
+            depth: section.depth,
+            heading: section.heading,
+            path: section.path,
+            range: section.range.start + offset..section.range.end + offset,
+            start: shift(section.start),
+            end: shift(section.end),
+        }
+    }
+}
+
+/// Split a stream of markdown into sections as it is read
+///
+/// Sections are yielded as soon as the line starting the next section is read, without reading the
+/// whole text into memory, e.g. for large concatenated documentation dumps or stdin pipelines. The
+/// sections are the same as those of [`split_sections_with`](crate::split_sections_with) on the
+/// whole text.
+///
+/// The lines which may start a section are ATX headings within the depth range, setext heading
+/// underlines, and thematic breaks or ends of HTML comments if they are [`SplitOptions::markers`].
+/// A section ending at a [`Marker::Custom`](crate::Marker::Custom) node or at a heading nested in a
+/// container is only yielded once such a line is read after it. A line which looks like it may
+/// start a section but does not, e.g. in an HTML block, delays the next attempt until the text
+/// read since then is as long as the pending text, so that the stream is parsed in linear time.
+///
+/// # Arguments
+///
+/// - `reader`: The stream of markdown text, which must be valid UTF-8.
+/// - `options`: An optional `ParseOptions` struct to configure the markdown parser. If `None`,
+///   `ParseOptions::gfm()` (GitHub Flavored Markdown) is used.
+/// - `split_options`: Options to configure how the text is split into sections.
+///
+/// # Returns
+///
+/// An iterator over the [`OwnedSection`]s of the text, which stops after the first error, e.g. if
+/// the stream cannot be read, or for the same reasons as `split_sections_with`.
+///
+/// ```
+/// use markdown_split::{split_reader, SplitOptions};
+///
+/// let text = "# Install\n\nRun it.\n\n# Update\n\nRun it again.\n";
+/// let sections = split_reader(text.as_bytes(), None, &SplitOptions::default())
+///     .collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(sections[1].text, "# Update\n\nRun it again.\n");
+/// # Ok::<(), markdown_split::Error>(())
+/// ```
+pub fn split_reader<'o, R: BufRead>(
+    reader: R,
+    options: Option<&'o ParseOptions>,
+    split_options: &SplitOptions,
+) -> SectionReader<'o, R> {
+    SectionReader {
+        reader,
+        splitter: Splitter::new(options, split_options),
+    }
+}
+
+/// An iterator over the sections of a stream of markdown, see [`split_reader`].
+pub struct SectionReader<'o, R> {
+    reader: R,
+    splitter: Splitter<'o>,
+}
+
+impl<R: BufRead> Iterator for SectionReader<'_, R> {
+    type Item = Result<OwnedSection>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(next) = self.splitter.poll() {
+                return next;
+            }
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line);
+            self.splitter.feed(read, &line);
+        }
+    }
+}
+
+/// Split an asynchronous stream of markdown into sections as it is read, see [`split_reader`].
+#[cfg(feature = "tokio")]
+pub fn split_async_reader<'o, R: tokio::io::AsyncBufRead + Unpin>(
+    reader: R,
+    options: Option<&'o ParseOptions>,
+    split_options: &SplitOptions,
+) -> AsyncSectionReader<'o, R> {
+    AsyncSectionReader {
+        reader,
+        splitter: Splitter::new(options, split_options),
+    }
+}
+
+/// The sections of an asynchronous stream of markdown, see [`split_async_reader`].
+#[cfg(feature = "tokio")]
+pub struct AsyncSectionReader<'o, R> {
+    reader: R,
+    splitter: Splitter<'o>,
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncBufRead + Unpin> AsyncSectionReader<'_, R> {
+    /// Read the next section, or `None` once the stream is over or after an error.
+    pub async fn next_section(&mut self) -> Option<Result<OwnedSection>> {
+        use tokio::io::AsyncBufReadExt;
+
+        loop {
+            if let Some(next) = self.splitter.poll() {
+                return next;
+            }
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line).await;
+            self.splitter.feed(read, &line);
+        }
+    }
+}
+
+/// Splits markdown text line by line, into sections which are known to be complete.
+struct Splitter<'o> {
+    options: Option<&'o ParseOptions>,
+    split_options: SplitOptions,
+    /// The text read but not split into sections yet, which starts at a section boundary.
+    buffer: String,
+    /// The byte offset of `buffer` in the whole text.
+    offset: usize,
+    /// The number of lines before `buffer` in the whole text.
+    lines: usize,
+    /// The character and the length of the fence of the code block `buffer` ends in, if any.
+    fence: Option<(char, usize)>,
+    /// The length of `buffer` when it was last parsed without yielding anything, or `0`.
+    attempted: usize,
+    /// The number of bytes parsed so far, which stays proportional to the length of the text.
+    parsed: usize,
+    /// The headings of the sections enclosing the current one, along with their depths.
+    ancestors: Vec<(u8, String)>,
+    /// The sections which are complete but not yielded yet.
+    ready: VecDeque<OwnedSection>,
+    /// The error to yield after the ready sections, if any.
+    error: Option<crate::Error>,
+    /// Whether the stream is over.
+    done: bool,
+}
+
+impl<'o> Splitter<'o> {
+    fn new(options: Option<&'o ParseOptions>, split_options: &SplitOptions) -> Self {
+        Self {
+            options,
+            split_options: split_options.clone(),
+            buffer: String::new(),
+            offset: 0,
+            lines: 0,
+            fence: None,
+            attempted: 0,
+            parsed: 0,
+            ancestors: vec![],
+            ready: VecDeque::new(),
+            error: None,
+            done: false,
+        }
+    }
+
+    /// The next item to yield, or `None` if another line needs to be read first.
+    fn poll(&mut self) -> Option<Option<Result<OwnedSection>>> {
+        if let Some(section) = self.ready.pop_front() {
+            return Some(Some(Ok(section)));
+        }
+        if let Some(error) = self.error.take() {
+            return Some(Some(Err(error)));
+        }
+        self.done.then_some(None)
+    }
+
+    /// Take the result of reading a line, which is empty at the end of the stream.
+    fn feed(&mut self, read: io::Result<usize>, line: &str) {
+        let result = match read {
+            Ok(0) => {
+                self.done = true;
+                self.finish()
+            }
+            Ok(_) => self.push_line(line),
+            Err(error) => Err(error.into()),
+        };
+        if let Err(error) = result {
+            self.error = Some(error);
+            self.done = true;
+        }
+    }
+
+    fn push_line(&mut self, line: &str) -> Result<()> {
+        let candidate = self.buffer.len();
+        self.buffer.push_str(line);
+
+        if let Some((c, n)) = self.fence {
+            // Nothing but the closing fence ends a fenced code block at the top level.
+            let trimmed = line.trim_start_matches(' ');
+            let count = trimmed.chars().take_while(|&ch| ch == c).count();
+            if line.len() - trimmed.len() < 4 && count >= n && trimmed[count..].trim().is_empty() {
+                self.fence = None;
+            }
+            return Ok(());
+        }
+        if candidate > 0
+            && self.buffer.len() >= 2 * self.attempted
+            && self.may_start_section(line)
+            && !self.in_front_matter()
+        {
+            self.cut()?;
+        }
+        Ok(())
+    }
+
+    /// Whether a line may start a section, or end the line which does, as far as can be told
+    /// without parsing it.
+    fn may_start_section(&self, line: &str) -> bool {
+        let options = &self.split_options;
+        let heading = match atx_depth(line).or_else(|| setext_depth(line)) {
+            Some(depth) => options.splits_on(depth),
+            None => false,
+        };
+        let marker = options.markers.iter().any(|marker| match marker {
+            Marker::ThematicBreak => is_thematic_break(line),
+            Marker::HtmlComment(_) => line.contains("-->"),
+            Marker::Custom(_) => false,
+        });
+        heading || marker
+    }
+
+    /// Whether the buffer may still turn out to start with a front matter, once its closing fence
+    /// is read.
+    fn in_front_matter(&self) -> bool {
+        if !self.split_options.front_matter || self.offset > 0 {
+            return false;
+        }
+        let mut lines = self.buffer.split_inclusive('\n').map(str::trim_end);
+        let fence = lines.next().unwrap_or_default();
+        matches!(fence, "---" | "+++") && !lines.any(|line| line == fence)
+    }
+
+    /// Yield the sections before the last one of the buffer, as what follows the start of a
+    /// section cannot change how the text before it is split. The last section may still grow.
+    fn cut(&mut self) -> Result<()> {
+        let ast = parse(&self.buffer, self.options, &self.split_options)?;
+        self.parsed += self.buffer.len();
+        let Root(root) = &ast else { return Ok(()) };
+        if let Some(code @ Code(_)) = root.children.last() {
+            // The line was in an unclosed code block, which is not worth parsing again until it is
+            // closed.
+            let source = &self.buffer[start(Some(code)).unwrap_or_default()..];
+            let source = source.trim_start_matches(' ');
+            let c = source.chars().next().unwrap_or(' ');
+            let n = source.chars().take_while(|&ch| ch == c).count();
+            if matches!(c, '`' | '~') && n >= 3 {
+                self.fence = Some((c, n));
+            }
+        }
+
+        let mut sections = self.sections(&ast);
+        let last = sections.pop();
+        let end = last.as_ref().map_or(0, |s| s.range.start - self.offset);
+        // Nothing is complete yet, or the buffer is only a front matter so far. The `markdown`
+        // crate may also parse the rest differently on its own, e.g. after a link reference
+        // definition.
+        if end == 0 || end == self.buffer.len() || !self.parses_alone(end, last.as_ref()) {
+            self.attempted = self.buffer.len();
+            return Ok(());
+        }
+        self.emit(sections);
+        self.advance(end);
+        self.attempted = 0;
+        // Only the very start of the text may be a front matter.
+        self.split_options.front_matter = false;
+        Ok(())
+    }
+
+    /// Whether the buffer from `end` on, parsed on its own, is the single section `last` found in
+    /// the whole buffer.
+    fn parses_alone(&mut self, end: usize, last: Option<&OwnedSection>) -> bool {
+        let rest = &self.buffer[end..];
+        let split_options = SplitOptions { front_matter: false, ..self.split_options.clone() };
+        let Ok(ast) = parse(rest, self.options, &split_options) else { return false };
+        self.parsed += rest.len();
+        match sections_from_ast(rest, &ast, &split_options).as_slice() {
+            [section] => last.is_some_and(|last| last.depth == section.depth),
+            _ => false,
+        }
+    }
+
+    /// Yield all the remaining sections at the end of the stream.
+    fn finish(&mut self) -> Result<()> {
+        let ast = parse(&self.buffer, self.options, &self.split_options)?;
+        self.parsed += self.buffer.len();
+        let sections = self.sections(&ast);
+        self.emit(sections);
+        self.advance(self.buffer.len());
+        Ok(())
+    }
+
+    /// Queue sections of the buffer, with the path of their headings in the whole text.
+    fn emit(&mut self, sections: Vec<OwnedSection>) {
+        for mut section in sections {
+            if let (Some(depth), Some(heading)) = (section.depth, &section.heading) {
+                while self.ancestors.last().is_some_and(|(d, _)| *d >= depth) {
+                    self.ancestors.pop();
+                }
+                self.ancestors.push((depth, heading.clone()));
+            }
+            section.path = self.ancestors.iter().map(|(_, h)| h.clone()).collect();
+            self.ready.push_back(section);
+        }
+    }
+
+    /// Split the start of the buffer into sections, located in the whole text.
+    fn sections(&self, ast: &Node) -> Vec<OwnedSection> {
+        sections_from_ast(&self.buffer, ast, &self.split_options)
+            .into_iter()
+            .map(|section| OwnedSection::new(section, self.offset, self.lines))
+            .collect()
+    }
+
+    /// Drop the first `end` bytes of the buffer, which were yielded.
+    fn advance(&mut self, end: usize) {
+        self.lines += self.buffer[..end].matches('\n').count();
+        self.offset += end;
+        self.buffer.drain(..end);
+    }
+}
+
+/// The offset where a node starts.
+fn start(node: Option<&Node>) -> Option<usize> {
+    Some(node?.position()?.start.offset)
+}
+
+/// The depth of a line which may be an ATX heading at the top level, e.g. `## Installation`.
+fn atx_depth(line: &str) -> Option<u8> {
+    let trimmed = line.trim_start_matches(' ');
+    let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+    let rest = &trimmed[hashes..];
+    (line.len() - trimmed.len() < 4
+        && (1..=6).contains(&hashes)
+        && (rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n'])))
+    .then_some(hashes as u8)
+}
+
+/// The depth of the heading a line may be the setext underline of, e.g. `===`.
+fn setext_depth(line: &str) -> Option<u8> {
+    let trimmed = line.trim_start_matches(' ');
+    let c = trimmed.chars().next()?;
+    let rest = trimmed.trim_start_matches(c);
+    (line.len() - trimmed.len() < 4 && matches!(c, '=' | '-') && rest.trim().is_empty())
+        .then_some(if c == '=' { 1 } else { 2 })
+}
+
+/// Whether a line may be a thematic break at the top level, e.g. `---` or `* * *`.
+fn is_thematic_break(line: &str) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    let Some(c @ ('-' | '*' | '_')) = trimmed.chars().next() else { return false };
+    line.len() - trimmed.len() < 4
+        && trimmed.chars().filter(|&ch| ch == c).count() >= 3
+        && trimmed.chars().all(|ch| ch == c || ch.is_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::read_to_string, io::BufReader};
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        join::tests::{both, markdown},
+        Error,
+    };
+
+    /// Split a text as a stream read a few bytes at a time.
+    fn streamed(text: &str, options: &SplitOptions) -> Result<Vec<OwnedSection>> {
+        let reader = BufReader::with_capacity(7, text.as_bytes());
+        split_reader(reader, None, options).collect()
+    }
+
+    /// The sections of a text split at once, as the stream yields them.
+    fn owned(sections: Vec<Section>) -> Vec<OwnedSection> {
+        sections.into_iter().map(|s| OwnedSection::new(s, 0, 0)).collect()
+    }
+
+    #[test]
+    fn test_fixtures() {
+        for path in [
+            "tests/fixtures/ch01-01-installation.en.md",
+            "tests/fixtures/ch01-01-installation.ja.md",
+        ] {
+            let text = read_to_string(path).unwrap();
+            let (expected, actual) = both(&text, &SplitOptions::default(), streamed).unwrap();
+            assert_eq!(actual.unwrap(), owned(expected));
+        }
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let text = "Intro\n\n````md\n# Not a heading\n```\n# Still not\n````\n# A\n\n<div>\n# Not either\n\n# B\n  ```\n# C\n";
+        let (expected, actual) = both(text, &SplitOptions::default(), streamed).unwrap();
+        let actual = actual.unwrap();
+        assert_eq!(actual, owned(expected));
+        assert_eq!(
+            actual.iter().map(|s| s.heading.as_deref()).collect::<Vec<_>>(),
+            vec![None, Some("A"), Some("B")]
+        );
+        assert_eq!(actual[2].path, vec!["B"]);
+        assert_eq!(actual[2].start, Location { line: 13, ..Default::default() });
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut sections = split_reader("".as_bytes(), None, &SplitOptions::default());
+        assert!(matches!(sections.next(), Some(Err(Error::EmptyInput))));
+        assert!(sections.next().is_none());
+
+        let mut sections = split_reader(&b"# A\n\n\xff\n"[..], None, &SplitOptions::default());
+        assert!(matches!(sections.next(), Some(Err(Error::Io(_)))));
+        assert!(sections.next().is_none());
+    }
+
+    #[test]
+    fn test_linear() {
+        // Neither headings outside the depth range nor lines which only look like they start a
+        // section, e.g. in an HTML block, make the whole pending text parsed again on every line.
+        let sub = "## Sub\n\nText.\n\n<div>\n# Not a heading\n</div>\n\n";
+        let text = format!("# Top\n\n{}", sub.repeat(3000));
+        let options = SplitOptions { max_depth: 1, ..Default::default() };
+        let mut sections = split_reader(text.as_bytes(), None, &options);
+        assert_eq!(sections.by_ref().count(), 1);
+        assert!(sections.splitter.parsed <= 4 * text.len(), "{}", sections.splitter.parsed);
+
+        let text = "# A\n\nText.\n\n".repeat(3000);
+        let mut sections = split_reader(text.as_bytes(), None, &SplitOptions::default());
+        assert_eq!(sections.by_ref().count(), 3000);
+        assert!(sections.splitter.parsed <= 4 * text.len(), "{}", sections.splitter.parsed);
+    }
+
+    #[test]
+    fn test_eager() {
+        // A section is yielded as soon as a setext underline or a marker ends it.
+        let options = SplitOptions {
+            markers: vec![Marker::ThematicBreak],
+            ..Default::default()
+        };
+        let mut splitter = Splitter::new(None, &options);
+        let mut yielded = vec![];
+        let lines =
+            ["A\n", "===\n", "\n", "Text.\n", "\n", "***\n", "\n", "Text.\n", "\n", "B\n", "---\n"];
+        for line in lines {
+            splitter.feed(Ok(line.len()), line);
+            while let Some(Some(section)) = splitter.poll() {
+                yielded.push((line, section.unwrap().text));
+            }
+        }
+        assert_eq!(
+            yielded,
+            vec![
+                ("***\n", "A\n===\n\nText.\n\n".to_string()),
+                ("---\n", "***\n\nText.\n\n".to_string())
+            ]
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.en.md").unwrap();
+        let reader = tokio::io::BufReader::with_capacity(16, text.as_bytes());
+        let mut sections = split_async_reader(reader, None, &SplitOptions::default());
+        let mut actual = vec![];
+        while let Some(section) = sections.next_section().await {
+            actual.push(section.unwrap());
+        }
+        let expected = crate::split_sections_with(&text, None, &SplitOptions::default()).unwrap();
+        assert_eq!(actual, owned(expected));
+    }
+
+    proptest! {
+        #[test]
+        fn test_split_reader(text in markdown()) {
+            let options = SplitOptions { comments: true, front_matter: true, ..Default::default() };
+            let markers = SplitOptions {
+                markers: vec![Marker::ThematicBreak, Marker::html_comment("^split$").unwrap()],
+                ..Default::default()
+            };
+            for options in [SplitOptions::default(), options, markers] {
+                if let Some((expected, actual)) = both(&text, &options, streamed) {
+                    let boundaries = |s: &OwnedSection| (s.range.clone(), s.depth, s.start, s.end);
+                    prop_assert_eq!(
+                        actual.unwrap().iter().map(boundaries).collect::<Vec<_>>(),
+                        owned(expected).iter().map(boundaries).collect::<Vec<_>>()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::ops::Range;
+
+use markdown::ParseOptions;
+
+use crate::{error::Result, options::SplitOptions, section::Section, split::split_sections_with};
+
+/// A node in the outline of a markdown text, i.e. a section together with the sections nested
+/// under its heading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SectionNode<'a> {
+    /// The section itself, whose text covers only its own body up to the next heading.
+    pub section: Section<'a>,
+    /// The slice of the original markdown text covered by this section and all of its descendants.
+    pub text: &'a str,
+    /// The byte range of this section and all of its descendants in the original markdown text.
+    pub range: Range<usize>,
+    /// The sections nested under this section's heading, in document order.
+    pub children: Vec<SectionNode<'a>>,
+}
+
+impl<'a> SectionNode<'a> {
+    fn new(section: Section<'a>) -> Self {
+        Self {
+            text: section.text,
+            range: section.range.clone(),
+            section,
+            children: vec![],
+        }
+    }
+}
+
+/// Split a markdown text into a tree of sections based on the depth of headings
+///
+/// A section becomes a child of the closest preceding section with a shallower heading, so an
+/// `###` nests under the preceding `##`. Skipped levels are fine: an `###` directly following an
+/// `#` still becomes its child. The text before the first heading, if any, is always a root, while
+/// other sections without a heading become leaves of the section they follow.
+///
+/// # Arguments
+///
+/// - `text`: A string slice containing the markdown text to split.
+/// - `options`: An optional `ParseOptions` struct to configure the markdown parser. If `None`,
+///   `ParseOptions::gfm()` (GitHub Flavored Markdown) is used.
+///
+/// # Returns
+///
+/// A vector of the root [`SectionNode`]s, in document order.
+///
+/// # Errors
+///
+/// Returns an error if the markdown text is empty or if the text cannot be parsed by the `markdown`
+/// crate.
+pub fn split_tree<'a>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+) -> Result<Vec<SectionNode<'a>>> {
+    split_tree_with(text, options, &SplitOptions::default())
+}
+
+/// Split a markdown text into a tree of sections, as configured by `split_options`
+///
+/// See [`split_tree`] for the arguments and the return value. Headings outside the configured
+/// depth range do not become nodes of the tree.
+///
+/// # Errors
+///
+/// Returns an error if the markdown text is empty, if the depth range in `split_options` is
+/// invalid, or if the text cannot be parsed by the `markdown` crate.
+pub fn split_tree_with<'a>(
+    text: &'a str,
+    options: Option<&ParseOptions>,
+    split_options: &SplitOptions,
+) -> Result<Vec<SectionNode<'a>>> {
+    Ok(build_tree(text, split_sections_with(text, options, split_options)?))
+}
+
+/// Nest a flat list of contiguous sections into a tree, using a stack of the currently open
+/// sections.
+pub(crate) fn build_tree<'a>(text: &'a str, sections: Vec<Section<'a>>) -> Vec<SectionNode<'a>> {
+    let mut roots = vec![];
+    let mut stack: Vec<SectionNode> = vec![];
+
+    for section in sections {
+        let Some(depth) = section.depth else {
+            // A section without a heading, i.e. the text before the first heading or one started
+            // by a marker, stays in the section it follows.
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(SectionNode::new(section)),
+                None => roots.push(SectionNode::new(section)),
+            }
+            continue;
+        };
+        while stack.last().is_some_and(|n| n.section.depth >= Some(depth)) {
+            let node = stack.pop().unwrap();
+            close(text, node, &mut stack, &mut roots);
+        }
+        stack.push(SectionNode::new(section));
+    }
+    while let Some(node) = stack.pop() {
+        close(text, node, &mut stack, &mut roots);
+    }
+
+    roots
+}
+
+/// Extend a finished node over its children and attach it to its parent, or to the roots.
+fn close<'a>(
+    text: &'a str,
+    mut node: SectionNode<'a>,
+    stack: &mut [SectionNode<'a>],
+    roots: &mut Vec<SectionNode<'a>>,
+) {
+    if let Some(last) = node.children.last() {
+        node.range.end = last.range.end;
+        node.text = &text[node.range.clone()];
+    }
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+
+    #[test]
+    fn test_en() {
+        let text = read_to_string("tests/fixtures/ch01-01-installation.en.md").unwrap();
+
+        let roots = split_tree(&text, None).unwrap();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].section.depth, None);
+        assert!(roots[0].children.is_empty());
+
+        let installation = &roots[1];
+        assert_eq!(installation.section.heading.as_deref(), Some("Installation"));
+        assert_eq!(installation.range, installation.section.range.start..text.len());
+        assert_eq!(installation.text, &text[installation.range.clone()]);
+        assert_eq!(
+            installation
+                .children
+                .iter()
+                .map(|c| c.section.heading.as_deref())
+                .collect::<Vec<_>>(),
+            vec![
+                Some("Installing rustup on Linux or macOS"),
+                Some("Installing rustup on Windows"),
+                Some("Troubleshooting"),
+                Some("Updating and Uninstalling"),
+                Some("Local Documentation"),
+            ]
+        );
+        assert!(installation.children.iter().all(|c| c.text == c.section.text));
+    }
+
+    #[test]
+    fn test_nesting() {
+        let text = "# A\n\n### B\n\n## C\n\n#### D\n\n# E\n";
+
+        let roots = split_tree(text, None).unwrap();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].text, "# A\n\n### B\n\n## C\n\n#### D\n\n");
+        assert_eq!(roots[0].section.text, "# A\n\n");
+        assert_eq!(roots[0].children.len(), 2);
+        assert_eq!(roots[0].children[0].text, "### B\n\n");
+        assert_eq!(roots[0].children[1].text, "## C\n\n#### D\n\n");
+        assert_eq!(roots[0].children[1].children[0].text, "#### D\n\n");
+        assert_eq!(roots[1].text, "# E\n");
+    }
+}